@@ -0,0 +1,635 @@
+// SPDX-License-Identifier: UNLICENSED
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+// Keep `core::fmt` panic formatting out of the deployed code
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+use alloc::vec::Vec;
+use alloy_sol_types::{eip712_domain, sol, Eip712Domain, SolCall, SolEvent, SolStruct, SolValue};
+use stylus_sdk::{
+    alloy_primitives::{address, Address, B256, U256, U8},
+    prelude::*,
+};
+
+// Relays EIP-712 permits signed by device owners and owner-approved consumer access
+// requests into DeviceAccess, which trusts this contract as a grant delegate
+sol_storage! {
+    #[entrypoint]
+    pub struct AccessBroker {
+        /// Admin and configuration
+        address admin;
+        address device_access_address;
+
+        /// Next EIP-712 permit nonce per owner
+        mapping(address => uint256) permit_nonces;
+
+        /// Consumer access requests awaiting owner decision
+        mapping(uint256 => AccessRequest) access_requests; // request_id -> request
+        uint256 access_request_count;
+    }
+
+    /// Consumer request for access (device_id_hash = 0 requests owner-wide access)
+    pub struct AccessRequest {
+        address consumer;
+        address owner;
+        bytes32 device_id_hash;
+        uint8 scope;
+        uint256 duration;
+        bytes32 purpose_hash;
+        uint8 status;
+    }
+}
+
+/// Access scope bits, as defined by DeviceAccess
+const ACCESS_SCOPE_RAW: u8 = 1 << 0; // Raw device readings
+const ACCESS_SCOPE_AGGREGATED: u8 = 1 << 1; // Aggregates computed in Cartesi
+const ACCESS_SCOPE_METADATA: u8 = 1 << 2; // Device metadata only
+const ACCESS_SCOPE_ALL: u8 = ACCESS_SCOPE_RAW | ACCESS_SCOPE_AGGREGATED | ACCESS_SCOPE_METADATA;
+
+/// Access request states (0 = no such request)
+const REQUEST_STATUS_PENDING: u8 = 1;
+const REQUEST_STATUS_APPROVED: u8 = 2;
+const REQUEST_STATUS_REJECTED: u8 = 3;
+const REQUEST_STATUS_CANCELLED: u8 = 4;
+
+/// Access request returned by `get_access_request`:
+/// (consumer, owner, device_id_hash, scope, duration, purpose_hash, status)
+type AccessRequestRecord = (Address, Address, B256, u8, U256, B256, u8);
+
+/// secp256k1 n / 2; ecrecover signatures with a higher s are malleable
+const SECP256K1_HALF_N: U256 = U256::from_be_slice(&[
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+]);
+
+const ECRECOVER_PRECOMPILE: Address = address!("0000000000000000000000000000000000000001");
+
+// EIP-712 permits signed by device owners and submitted by relayers
+sol! {
+    struct GrantAccess {
+        address owner;
+        address consumer;
+        uint256 expires_at;
+        uint256 deadline;
+        uint256 nonce;
+    }
+
+    struct RevokeAccess {
+        address owner;
+        address consumer;
+        uint256 deadline;
+        uint256 nonce;
+    }
+}
+
+sol! {
+    event AccessRequested(
+        uint256 indexed request_id,
+        address indexed owner,
+        address indexed consumer,
+        bytes32 device_id_hash,
+        uint8 scope,
+        uint256 duration,
+        bytes32 purpose_hash
+    );
+
+    event AccessRequestResolved(
+        uint256 indexed request_id,
+        address indexed owner,
+        address indexed consumer,
+        uint8 status
+    );
+}
+
+// Interface for DeviceAccess contract calls
+sol! {
+    interface IDeviceAccess {
+        function grant_access_for(address owner, address consumer, uint256 expires_at) external;
+        function revoke_access_for(address owner, address consumer) external;
+        function grant_access_scope_for(address owner, address consumer, uint8 scopes, uint256 expires_at) external;
+        function grant_device_access_scope_for(bytes32 device_id_hash, address owner, address consumer, uint8 scopes, uint256 expires_at) external;
+    }
+}
+
+#[public]
+impl AccessBroker {
+    /// Initialize the contract with the DeviceAccess contract it relays permits to
+    pub fn initialize(&mut self, device_access: Address) -> Result<(), Vec<u8>> {
+        if self.admin.get() != Address::ZERO {
+            return Err(b"Already initialized".to_vec());
+        }
+        self.admin.set(self.vm().msg_sender());
+        self.device_access_address.set(device_access);
+        Ok(())
+    }
+
+    // ========== Permits ==========
+
+    /// Grant data access using an EIP-712 `GrantAccess` permit signed by the owner (relayer submits)
+    #[allow(clippy::too_many_arguments)]
+    pub fn grant_access_with_signature(
+        &mut self,
+        owner: Address,
+        consumer: Address,
+        expires_at: U256,
+        deadline: U256,
+        nonce: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Vec<u8>> {
+        if consumer == Address::ZERO {
+            return Err(b"Invalid consumer address".to_vec());
+        }
+        let permit = GrantAccess { owner, consumer, expires_at, deadline, nonce };
+        let digest = permit.eip712_signing_hash(&self.eip712_domain());
+        self.use_permit(owner, deadline, nonce, digest, v, r, s)?;
+        self.device_access_call(&IDeviceAccess::grant_access_forCall { owner, consumer, expires_at }.abi_encode())
+    }
+
+    /// Revoke data access using an EIP-712 `RevokeAccess` permit signed by the owner (relayer submits)
+    #[allow(clippy::too_many_arguments)]
+    pub fn revoke_access_with_signature(
+        &mut self,
+        owner: Address,
+        consumer: Address,
+        deadline: U256,
+        nonce: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Vec<u8>> {
+        let permit = RevokeAccess { owner, consumer, deadline, nonce };
+        let digest = permit.eip712_signing_hash(&self.eip712_domain());
+        self.use_permit(owner, deadline, nonce, digest, v, r, s)?;
+        self.device_access_call(&IDeviceAccess::revoke_access_forCall { owner, consumer }.abi_encode())
+    }
+
+    /// Get the next EIP-712 permit nonce for an owner
+    pub fn nonces(&self, owner: Address) -> Result<U256, Vec<u8>> {
+        Ok(self.permit_nonces.getter(owner).get())
+    }
+
+    /// Get the EIP-712 domain separator
+    pub fn domain_separator(&self) -> Result<B256, Vec<u8>> {
+        Ok(self.eip712_domain().separator())
+    }
+
+    // ========== Access Requests ==========
+
+    /// Request access to an owner's data (called by consumer); returns the request id
+    /// `device_id_hash` = 0 requests owner-wide access; `duration` = 0 requests permanent access
+    pub fn request_access(
+        &mut self,
+        owner: Address,
+        device_id_hash: B256,
+        scope: u8,
+        duration: U256,
+        purpose_hash: B256,
+    ) -> Result<U256, Vec<u8>> {
+        if owner == Address::ZERO {
+            return Err(b"Invalid owner address".to_vec());
+        }
+        if scope == 0 || scope & !ACCESS_SCOPE_ALL != 0 {
+            return Err(b"Invalid access scope".to_vec());
+        }
+
+        let consumer = self.vm().msg_sender();
+        let request_id = self.access_request_count.get();
+        self.access_request_count.set(request_id + U256::from(1));
+
+        let mut request = self.access_requests.setter(request_id);
+        request.consumer.set(consumer);
+        request.owner.set(owner);
+        request.device_id_hash.set(device_id_hash);
+        request.scope.set(U8::from(scope));
+        request.duration.set(duration);
+        request.purpose_hash.set(purpose_hash);
+        request.status.set(U8::from(REQUEST_STATUS_PENDING));
+
+        self.emit(AccessRequested {
+            request_id,
+            owner,
+            consumer,
+            device_id_hash,
+            scope,
+            duration,
+            purpose_hash,
+        });
+
+        Ok(request_id)
+    }
+
+    /// Approve a pending request, granting access for the requested duration (called by owner)
+    /// Device requests fail if the device has changed hands since the request
+    pub fn approve_access_request(&mut self, request_id: U256) -> Result<(), Vec<u8>> {
+        let (consumer, owner, device_id_hash, scope, duration) = {
+            let request = self.access_requests.getter(request_id);
+            (
+                request.consumer.get(),
+                request.owner.get(),
+                request.device_id_hash.get(),
+                request.scope.get().to::<u8>(),
+                request.duration.get(),
+            )
+        };
+        self.resolve_access_request(request_id, owner, REQUEST_STATUS_APPROVED)?;
+
+        let expires_at = if duration.is_zero() {
+            U256::ZERO
+        } else {
+            U256::from(self.vm().block_timestamp()).saturating_add(duration)
+        };
+
+        let calldata = if !device_id_hash.is_zero() {
+            IDeviceAccess::grant_device_access_scope_forCall { device_id_hash, owner, consumer, scopes: scope, expires_at }
+                .abi_encode()
+        } else if scope == ACCESS_SCOPE_ALL {
+            IDeviceAccess::grant_access_forCall { owner, consumer, expires_at }.abi_encode()
+        } else {
+            IDeviceAccess::grant_access_scope_forCall { owner, consumer, scopes: scope, expires_at }.abi_encode()
+        };
+        self.device_access_call(&calldata)
+    }
+
+    /// Reject a pending request (called by owner)
+    pub fn reject_access_request(&mut self, request_id: U256) -> Result<(), Vec<u8>> {
+        let owner = self.access_requests.getter(request_id).owner.get();
+        self.resolve_access_request(request_id, owner, REQUEST_STATUS_REJECTED)
+    }
+
+    /// Withdraw a pending request (called by consumer)
+    pub fn cancel_access_request(&mut self, request_id: U256) -> Result<(), Vec<u8>> {
+        let consumer = self.access_requests.getter(request_id).consumer.get();
+        self.resolve_access_request(request_id, consumer, REQUEST_STATUS_CANCELLED)
+    }
+
+    /// Get an access request: (consumer, owner, device_id_hash, scope, duration, purpose_hash, status)
+    pub fn get_access_request(&self, request_id: U256) -> Result<AccessRequestRecord, Vec<u8>> {
+        let request = self.access_requests.getter(request_id);
+        Ok((
+            request.consumer.get(),
+            request.owner.get(),
+            request.device_id_hash.get(),
+            request.scope.get().to::<u8>(),
+            request.duration.get(),
+            request.purpose_hash.get(),
+            request.status.get().to::<u8>(),
+        ))
+    }
+
+    /// Get total number of access requests (request ids are `0..count`)
+    pub fn access_request_count(&self) -> Result<U256, Vec<u8>> {
+        Ok(self.access_request_count.get())
+    }
+
+    // ========== Admin Functions ==========
+
+    /// Get contract owner
+    pub fn owner(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.admin.get())
+    }
+
+    /// Get the DeviceAccess contract permits are relayed to
+    pub fn device_access(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.device_access_address.get())
+    }
+
+    /// Update DeviceAccess address
+    pub fn set_device_access(&mut self, device_access: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.device_access_address.set(device_access);
+        Ok(())
+    }
+}
+
+// Private helper functions
+impl AccessBroker {
+    /// Emit an event; unlike `stylus_sdk::prelude::log` this cannot panic, which keeps
+    /// panic formatting out of the contract
+    fn emit<T: SolEvent>(&self, event: T) {
+        let mut topics = [alloy_sol_types::abi::token::WordToken::default(); 4];
+        let _ = event.encode_topics_raw(&mut topics);
+        let count = <T::TopicList as alloy_sol_types::TopicList>::COUNT;
+        let mut data = Vec::with_capacity(32 * count);
+        for topic in &topics[..count] {
+            data.extend_from_slice(topic.as_slice());
+        }
+        event.encode_data_to(&mut data);
+        self.vm().emit_log(&data, count);
+    }
+
+    fn only_owner(&self) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != self.admin.get() {
+            return Err(b"Only owner can call this function".to_vec());
+        }
+        Ok(())
+    }
+
+    /// EIP-712 domain permits are signed under
+    fn eip712_domain(&self) -> Eip712Domain {
+        eip712_domain! {
+            name: "LCORE AccessBroker",
+            version: "1",
+            chain_id: self.vm().chain_id(),
+            verifying_contract: self.vm().contract_address(),
+        }
+    }
+
+    /// Check deadline, nonce and owner signature of a permit, then consume the nonce
+    #[allow(clippy::too_many_arguments)]
+    fn use_permit(
+        &mut self,
+        owner: Address,
+        deadline: U256,
+        nonce: U256,
+        digest: B256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Vec<u8>> {
+        if U256::from(self.vm().block_timestamp()) > deadline {
+            return Err(b"Permit expired".to_vec());
+        }
+        let expected_nonce = self.permit_nonces.getter(owner).get();
+        if nonce != expected_nonce {
+            return Err(b"Invalid permit nonce".to_vec());
+        }
+        if owner == Address::ZERO || self.recover_signer(digest, v, r, s) != Some(owner) {
+            return Err(b"Invalid permit signature".to_vec());
+        }
+        self.permit_nonces.setter(owner).set(expected_nonce + U256::from(1));
+        Ok(())
+    }
+
+    /// Recover the secp256k1 signer of `hash` via the ecrecover precompile
+    fn recover_signer(&self, hash: B256, v: u8, r: B256, s: B256) -> Option<Address> {
+        // Reject malleable (high-s) signatures
+        if U256::from_be_bytes(s.0) > SECP256K1_HALF_N {
+            return None;
+        }
+        let v = if v < 27 { v + 27 } else { v };
+        let calldata = (hash, U256::from(v), r, s).abi_encode();
+        let response = self.vm().static_call(&self, ECRECOVER_PRECOMPILE, &calldata).ok()?;
+        if response.len() != 32 {
+            return None;
+        }
+        let signer = Address::from_slice(&response[12..32]);
+        (signer != Address::ZERO).then_some(signer)
+    }
+
+    /// Move a pending request to a final status; only `authorized` may resolve it
+    fn resolve_access_request(&mut self, request_id: U256, authorized: Address, status: u8) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != authorized {
+            return Err(b"Not authorized for request".to_vec());
+        }
+        let mut request = self.access_requests.setter(request_id);
+        if request.status.get().to::<u8>() != REQUEST_STATUS_PENDING {
+            return Err(b"Request not pending".to_vec());
+        }
+        request.status.set(U8::from(status));
+        let owner = request.owner.get();
+        let consumer = request.consumer.get();
+
+        self.emit(AccessRequestResolved {
+            request_id,
+            owner,
+            consumer,
+            status,
+        });
+
+        Ok(())
+    }
+
+    /// Call into DeviceAccess, passing its rejections back to the relayer
+    fn device_access_call(&mut self, calldata: &[u8]) -> Result<(), Vec<u8>> {
+        let device_access = self.device_access_address.get();
+        self.vm().call(&self, device_access, calldata)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::{alloy_primitives::hex, crypto, testing::*};
+
+    const ADMIN: Address = address!("0000000000000000000000000000000000000002");
+    const OWNER: Address = address!("0000000000000000000000000000000000000003");
+    const CONSUMER: Address = address!("0000000000000000000000000000000000000004");
+    const DEVICE_ACCESS: Address = address!("0000000000000000000000000000000000000005");
+
+    /// Run `f` with `sender` as msg.sender
+    fn with_sender<R>(vm: &TestVM, sender: Address, f: impl FnOnce() -> R) -> R {
+        vm.set_sender(sender);
+        f()
+    }
+
+    fn setup(vm: &TestVM) -> AccessBroker {
+        let mut contract = AccessBroker::from(vm);
+        with_sender(vm, ADMIN, || assert!(contract.initialize(DEVICE_ACCESS).is_ok()));
+        contract
+    }
+
+    /// Mock ecrecover so that (r, s, v = 27) over `digest` recovers `signer`
+    fn mock_signature(vm: &TestVM, digest: B256, signer: Address) -> (u8, B256, B256) {
+        let r = crypto::keccak(digest.as_slice());
+        let s = B256::left_padding_from(&[0x01]);
+        let calldata = (digest, U256::from(27), r, s).abi_encode();
+        vm.mock_static_call(ECRECOVER_PRECOMPILE, calldata, Ok(signer.abi_encode()));
+        (27, r, s)
+    }
+
+    #[test]
+    fn test_access_permits() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let relayer = address!("0000000000000000000000000000000000000007");
+        let deadline = U256::from(1_000);
+        vm.set_block_timestamp(500);
+
+        let permit = GrantAccess { owner: OWNER, consumer: CONSUMER, expires_at: U256::ZERO, deadline, nonce: U256::ZERO };
+        let digest = permit.eip712_signing_hash(&contract.eip712_domain());
+        let (v, r, s) = mock_signature(&vm, digest, OWNER);
+        let grant = IDeviceAccess::grant_access_forCall { owner: OWNER, consumer: CONSUMER, expires_at: U256::ZERO };
+
+        with_sender(&vm, relayer, || {
+            // The signature only covers the signed fields
+            assert!(contract
+                .grant_access_with_signature(OWNER, CONSUMER, U256::from(1), deadline, U256::ZERO, v, r, s)
+                .is_err());
+            assert!(contract
+                .grant_access_with_signature(OWNER, CONSUMER, U256::ZERO, deadline, U256::from(1), v, r, s)
+                .is_err());
+
+            vm.mock_call(DEVICE_ACCESS, grant.abi_encode(), Ok(Vec::new()));
+            assert!(contract
+                .grant_access_with_signature(OWNER, CONSUMER, U256::ZERO, deadline, U256::ZERO, v, r, s)
+                .is_ok());
+            assert_eq!(contract.nonces(OWNER).unwrap(), U256::from(1));
+
+            // Permits cannot be replayed
+            assert!(contract
+                .grant_access_with_signature(OWNER, CONSUMER, U256::ZERO, deadline, U256::ZERO, v, r, s)
+                .is_err());
+        });
+
+        let permit = RevokeAccess { owner: OWNER, consumer: CONSUMER, deadline, nonce: U256::from(1) };
+        let digest = permit.eip712_signing_hash(&contract.eip712_domain());
+        let (v, r, s) = mock_signature(&vm, digest, OWNER);
+        let revoke = IDeviceAccess::revoke_access_forCall { owner: OWNER, consumer: CONSUMER };
+        vm.mock_call(DEVICE_ACCESS, revoke.abi_encode(), Ok(Vec::new()));
+
+        with_sender(&vm, relayer, || {
+            // Expired permits are rejected
+            vm.set_block_timestamp(1_001);
+            assert!(contract.revoke_access_with_signature(OWNER, CONSUMER, deadline, U256::from(1), v, r, s).is_err());
+
+            // Signatures by anyone but the owner are rejected
+            assert!(contract.revoke_access_with_signature(CONSUMER, CONSUMER, deadline, U256::ZERO, v, r, s).is_err());
+
+            vm.set_block_timestamp(1_000);
+            assert!(contract.revoke_access_with_signature(OWNER, CONSUMER, deadline, U256::from(1), v, r, s).is_ok());
+            assert_eq!(contract.nonces(OWNER).unwrap(), U256::from(2));
+        });
+
+        // DeviceAccess rejections are passed back to the relayer
+        let permit = RevokeAccess { owner: OWNER, consumer: CONSUMER, deadline, nonce: U256::from(2) };
+        let digest = permit.eip712_signing_hash(&contract.eip712_domain());
+        let (v, r, s) = mock_signature(&vm, digest, OWNER);
+        vm.mock_call(DEVICE_ACCESS, revoke.abi_encode(), Err(b"Not authorized for owner".to_vec()));
+        with_sender(&vm, relayer, || {
+            let result = contract.revoke_access_with_signature(OWNER, CONSUMER, deadline, U256::from(2), v, r, s);
+            assert_eq!(result.unwrap_err(), b"Not authorized for owner".to_vec());
+        });
+    }
+
+    #[test]
+    fn test_permit_digest_is_domain_bound() {
+        let vm = TestVM::default();
+        let contract = setup(&vm);
+        let permit = GrantAccess { owner: OWNER, consumer: CONSUMER, expires_at: U256::ZERO, deadline: U256::from(1_000), nonce: U256::ZERO };
+        let domain = |chain_id: u64| {
+            eip712_domain! {
+                name: "LCORE AccessBroker",
+                version: "1",
+                chain_id: chain_id,
+                verifying_contract: address!("0000000000000000000000000000000000000100"),
+            }
+        };
+
+        assert_eq!(
+            GrantAccess::eip712_encode_type(),
+            "GrantAccess(address owner,address consumer,uint256 expires_at,uint256 deadline,uint256 nonce)"
+        );
+        assert_ne!(permit.eip712_signing_hash(&domain(1)), permit.eip712_signing_hash(&domain(2)));
+        assert_eq!(contract.domain_separator().unwrap(), contract.eip712_domain().separator());
+        assert_eq!(SECP256K1_HALF_N * U256::from(2) + U256::from(1), U256::from_be_bytes(hex!(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141"
+        )));
+    }
+
+    #[test]
+    fn test_access_request_workflow() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let purpose = crypto::keccak(b"air quality research");
+        let meter = crypto::keccak(b"did:lcore:meter");
+        vm.set_block_timestamp(1_000);
+
+        let (approved_id, rejected_id, device_id) = with_sender(&vm, CONSUMER, || {
+            assert!(contract.request_access(Address::ZERO, B256::ZERO, ACCESS_SCOPE_RAW, U256::ZERO, purpose).is_err());
+            assert!(contract.request_access(OWNER, B256::ZERO, 0, U256::ZERO, purpose).is_err());
+            assert!(contract.request_access(OWNER, B256::ZERO, 0x80, U256::ZERO, purpose).is_err());
+
+            let approved_id = contract
+                .request_access(OWNER, B256::ZERO, ACCESS_SCOPE_AGGREGATED, U256::from(3600), purpose)
+                .unwrap();
+            let rejected_id = contract
+                .request_access(OWNER, B256::ZERO, ACCESS_SCOPE_RAW, U256::ZERO, purpose)
+                .unwrap();
+            let device_id = contract
+                .request_access(OWNER, meter, ACCESS_SCOPE_METADATA, U256::ZERO, purpose)
+                .unwrap();
+
+            // Consumers cannot approve their own requests
+            assert!(contract.approve_access_request(approved_id).is_err());
+            (approved_id, rejected_id, device_id)
+        });
+        assert_eq!(contract.access_request_count().unwrap(), U256::from(3));
+        assert_eq!(
+            contract.get_access_request(approved_id).unwrap(),
+            (CONSUMER, OWNER, B256::ZERO, ACCESS_SCOPE_AGGREGATED, U256::from(3600), purpose, REQUEST_STATUS_PENDING)
+        );
+
+        // Approved owner-wide requests grant the requested scope until now + duration
+        let grant = IDeviceAccess::grant_access_scope_forCall {
+            owner: OWNER,
+            consumer: CONSUMER,
+            scopes: ACCESS_SCOPE_AGGREGATED,
+            expires_at: U256::from(4_600),
+        };
+        vm.mock_call(DEVICE_ACCESS, grant.abi_encode(), Ok(Vec::new()));
+        let grant = IDeviceAccess::grant_device_access_scope_forCall {
+            device_id_hash: meter,
+            owner: OWNER,
+            consumer: CONSUMER,
+            scopes: ACCESS_SCOPE_METADATA,
+            expires_at: U256::ZERO,
+        };
+        vm.mock_call(DEVICE_ACCESS, grant.abi_encode(), Err(b"Device not owned by owner".to_vec()));
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.approve_access_request(approved_id).is_ok());
+            assert!(contract.reject_access_request(rejected_id).is_ok());
+
+            // Requests can only be resolved once
+            assert!(contract.approve_access_request(rejected_id).is_err());
+            assert!(contract.reject_access_request(approved_id).is_err());
+
+            // Device requests fail once the device has changed hands
+            let result = contract.approve_access_request(device_id);
+            assert_eq!(result.unwrap_err(), b"Device not owned by owner".to_vec());
+        });
+        assert_eq!(contract.get_access_request(approved_id).unwrap().6, REQUEST_STATUS_APPROVED);
+        assert_eq!(contract.get_access_request(rejected_id).unwrap().6, REQUEST_STATUS_REJECTED);
+
+        // Only the consumer withdraws its own request
+        let cancelled_id = with_sender(&vm, CONSUMER, || {
+            contract.request_access(OWNER, B256::ZERO, ACCESS_SCOPE_ALL, U256::ZERO, purpose).unwrap()
+        });
+        with_sender(&vm, OWNER, || assert!(contract.cancel_access_request(cancelled_id).is_err()));
+        with_sender(&vm, CONSUMER, || assert!(contract.cancel_access_request(cancelled_id).is_ok()));
+        assert_eq!(contract.get_access_request(cancelled_id).unwrap().6, REQUEST_STATUS_CANCELLED);
+
+        // Requests that were never made cannot be resolved
+        with_sender(&vm, Address::ZERO, || assert!(contract.approve_access_request(U256::from(99)).is_err()));
+    }
+
+    #[test]
+    fn test_admin_functions() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let new_device_access = address!("0000000000000000000000000000000000000006");
+
+        with_sender(&vm, ADMIN, || {
+            assert!(contract.initialize(DEVICE_ACCESS).is_err());
+            assert_eq!(contract.owner().unwrap(), ADMIN);
+            assert_eq!(contract.device_access().unwrap(), DEVICE_ACCESS);
+            assert!(contract.set_device_access(new_device_access).is_ok());
+            assert_eq!(contract.device_access().unwrap(), new_device_access);
+        });
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.set_device_access(DEVICE_ACCESS).is_err());
+        });
+    }
+}
@@ -0,0 +1,605 @@
+// SPDX-License-Identifier: UNLICENSED
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+// Keep `core::fmt` panic formatting out of the deployed code
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall, SolEvent, SolValue};
+use stylus_sdk::{
+    alloy_primitives::{hex, Address},
+    prelude::*,
+};
+
+// Decodes Cartesi voucher payloads and registers the device in the DeviceRegistry,
+// which only accepts `register_device_for` from its trusted rollup callers
+sol_storage! {
+    #[entrypoint]
+    pub struct CartesiGateway {
+        /// Admin and configuration
+        address admin;
+        address device_registry_address;
+
+        /// Trusted rollup callers (CartesiDApp addresses executing vouchers)
+        mapping(address => bool) rollup_callers;
+    }
+}
+
+/// Cartesi payload version byte for `abi.encode(CartesiSignedRegistration)`
+const PAYLOAD_VERSION_ABI: u8 = 0x01;
+/// Legacy Cartesi backends send a bare JSON object, so its opening brace acts as the version byte
+const PAYLOAD_VERSION_JSON: u8 = b'{';
+
+/// Deepest array/object nesting accepted in legacy JSON payloads
+const JSON_MAX_DEPTH: usize = 32;
+
+// Registration payload emitted by the Cartesi backend
+sol! {
+    struct CartesiDeviceRegistration {
+        string device_id;
+        string did_document;
+        string public_key;
+        string device_type;
+        string metadata;
+        address owner;
+    }
+
+    /// Registration with the device key's proof of possession (see `DeviceRegistry.register_device`)
+    struct CartesiSignedRegistration {
+        CartesiDeviceRegistration registration;
+        bytes proof;
+    }
+}
+
+// Interface for DeviceRegistry contract calls
+sol! {
+    interface IDeviceRegistry {
+        function register_device_for(
+            address owner,
+            string device_id,
+            string did_document,
+            string public_key_hex,
+            string device_type,
+            string metadata,
+            bytes proof
+        ) external;
+    }
+}
+
+sol! {
+    event RollupCallerUpdated(
+        address indexed rollup,
+        bool trusted
+    );
+}
+
+#[public]
+impl CartesiGateway {
+    /// Initialize the gateway with the DeviceRegistry it registers devices in
+    pub fn initialize(&mut self, device_registry: Address) -> Result<(), Vec<u8>> {
+        if self.admin.get() != Address::ZERO {
+            return Err(b"Already initialized".to_vec());
+        }
+        self.admin.set(self.vm().msg_sender());
+        self.device_registry_address.set(device_registry);
+        Ok(())
+    }
+
+    /// Register device from Cartesi rollup (called by rollup contract via voucher)
+    /// Payload is `0x01 ++ abi.encode(CartesiSignedRegistration)`, or a legacy JSON object with a hex `proof`
+    pub fn register_device_from_cartesi(&mut self, payload: Vec<u8>) -> Result<(), Vec<u8>> {
+        // Only trusted Cartesi rollups can register on behalf of an owner
+        if !self.rollup_callers.getter(self.vm().msg_sender()).get() {
+            return Err(b"Unauthorized: only rollup can register".to_vec());
+        }
+
+        let CartesiSignedRegistration {
+            registration:
+                CartesiDeviceRegistration {
+                    device_id,
+                    did_document,
+                    public_key,
+                    device_type,
+                    metadata,
+                    owner,
+                },
+            proof,
+        } = Self::decode_cartesi_payload(&payload)?;
+
+        // Use extracted owner address instead of msg_sender
+        let calldata = IDeviceRegistry::register_device_forCall {
+            owner,
+            device_id,
+            did_document,
+            public_key_hex: public_key,
+            device_type,
+            metadata,
+            proof,
+        }
+        .abi_encode();
+        let registry = self.device_registry_address.get();
+        self.vm().call(&self, registry, &calldata)?;
+        Ok(())
+    }
+
+    // ========== Admin Functions ==========
+
+    /// Get contract owner
+    pub fn owner(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.admin.get())
+    }
+
+    /// Get the DeviceRegistry devices are registered in
+    pub fn device_registry(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.device_registry_address.get())
+    }
+
+    /// Update DeviceRegistry address
+    pub fn set_device_registry(&mut self, device_registry: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.device_registry_address.set(device_registry);
+        Ok(())
+    }
+
+    /// Add or remove a trusted rollup caller for `register_device_from_cartesi`
+    pub fn set_rollup_caller(&mut self, rollup: Address, trusted: bool) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if rollup == Address::ZERO {
+            return Err(b"Invalid rollup address".to_vec());
+        }
+        self.rollup_callers.setter(rollup).set(trusted);
+        self.emit(RollupCallerUpdated { rollup, trusted });
+        Ok(())
+    }
+
+    /// Check if an address is a trusted rollup caller
+    pub fn is_rollup_caller(&self, rollup: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.rollup_callers.getter(rollup).get())
+    }
+}
+
+// Private helper functions
+impl CartesiGateway {
+    /// Emit an event; unlike `stylus_sdk::prelude::log` this cannot panic, which keeps
+    /// panic formatting out of the contract
+    fn emit<T: SolEvent>(&self, event: T) {
+        let mut topics = [alloy_sol_types::abi::token::WordToken::default(); 4];
+        let _ = event.encode_topics_raw(&mut topics);
+        let count = <T::TopicList as alloy_sol_types::TopicList>::COUNT;
+        let mut data = Vec::with_capacity(32 * count);
+        for topic in &topics[..count] {
+            data.extend_from_slice(topic.as_slice());
+        }
+        event.encode_data_to(&mut data);
+        self.vm().emit_log(&data, count);
+    }
+
+    /// Ensure only contract owner can call
+    fn only_owner(&self) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != self.admin.get() {
+            return Err(b"Only owner can call this function".to_vec());
+        }
+        Ok(())
+    }
+
+    /// Decode a versioned Cartesi registration payload (leading whitespace is ignored)
+    fn decode_cartesi_payload(payload: &[u8]) -> Result<CartesiSignedRegistration, Vec<u8>> {
+        let payload = payload.trim_ascii_start();
+        match payload.first() {
+            Some(&PAYLOAD_VERSION_ABI) => CartesiSignedRegistration::abi_decode(&payload[1..], true)
+                .map_err(|_| b"Bad ABI payload".to_vec()),
+            Some(&PAYLOAD_VERSION_JSON) => Self::decode_legacy_json(payload),
+            _ => Err(b"Unknown payload version".to_vec()),
+        }
+    }
+
+    /// Decode a legacy JSON registration payload (strict JSON, string values)
+    /// `did_document` and `metadata` may also be nested JSON, kept as their JSON text
+    fn decode_legacy_json(payload: &[u8]) -> Result<CartesiSignedRegistration, Vec<u8>> {
+        let members = Self::json_object_members(payload).ok_or(b"Bad JSON".to_vec())?;
+
+        let raw_field = |key: &str| members.iter().find(|(name, _)| name == key).map(|(_, raw)| *raw);
+        let string_field = |key: &str, error: &[u8]| -> Result<Option<String>, Vec<u8>> {
+            match raw_field(key) {
+                None => Ok(None),
+                Some(raw) => Self::json_string(raw).map(|(value, _)| Some(value)).ok_or(error.to_vec()),
+            }
+        };
+        let document_field = |key: &str| {
+            raw_field(key).and_then(|raw| match Self::json_string(raw) {
+                Some((value, _)) => Some(value),
+                None => String::from_utf8(raw.to_vec()).ok(),
+            })
+        };
+
+        let device_id = string_field("device_id", b"Bad device_id")?.ok_or(b"No device_id".to_vec())?;
+        let did_document = document_field("did_document").ok_or(b"No did_document".to_vec())?;
+        let public_key = string_field("public_key", b"Bad public_key")?.unwrap_or_default();
+        let device_type = string_field("device_type", b"Bad device_type")?.unwrap_or("iot".to_string());
+        let metadata = document_field("metadata").unwrap_or("{}".to_string());
+
+        // Extract owner address from payload (NEW: Fix ownership tracking)
+        let owner = string_field("owner_address", b"Bad owner_address")?
+            .ok_or(b"No owner_address".to_vec())?
+            .parse::<Address>()
+            .map_err(|_| b"Invalid owner address".to_vec())?;
+        let proof = string_field("proof", b"Bad proof")?.ok_or(b"No proof".to_vec())?;
+        let proof = hex::decode(proof).map_err(|_| b"Bad proof".to_vec())?;
+
+        Ok(CartesiSignedRegistration {
+            registration: CartesiDeviceRegistration {
+                device_id,
+                did_document,
+                public_key,
+                device_type,
+                metadata,
+                owner,
+            },
+            proof: proof.into(),
+        })
+    }
+
+    // ========== Minimal JSON ==========
+    // Strict RFC 8259 subset: duplicate object keys are rejected
+
+    /// Members of a JSON object as (key, raw value bytes); None unless `text` is exactly one object
+    fn json_object_members(text: &[u8]) -> Option<Vec<(String, &[u8])>> {
+        let start = Self::json_skip_ws(text, 0);
+        let mut members = Vec::new();
+        let end = Self::json_skip_object(text, start, JSON_MAX_DEPTH, Some(&mut members))?;
+        (Self::json_skip_ws(text, end) == text.len()).then_some(members)
+    }
+
+    /// Index of the first non-whitespace byte at or after `i`
+    fn json_skip_ws(bytes: &[u8], mut i: usize) -> usize {
+        while matches!(bytes.get(i), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            i += 1;
+        }
+        i
+    }
+
+    /// End of the JSON value starting at `i`, nesting at most `depth` arrays/objects
+    fn json_skip_value(bytes: &[u8], i: usize, depth: usize) -> Option<usize> {
+        match bytes.get(i)? {
+            b'"' => Self::json_string(bytes.get(i..)?).map(|(_, len)| i + len),
+            b'{' => Self::json_skip_object(bytes, i, depth, None),
+            b'[' => {
+                let depth = depth.checked_sub(1)?;
+                let mut i = Self::json_skip_ws(bytes, i + 1);
+                if bytes.get(i) == Some(&b']') {
+                    return Some(i + 1);
+                }
+                loop {
+                    i = Self::json_skip_ws(bytes, Self::json_skip_value(bytes, i, depth)?);
+                    match bytes.get(i)? {
+                        b',' => i = Self::json_skip_ws(bytes, i + 1),
+                        b']' => return Some(i + 1),
+                        _ => return None,
+                    }
+                }
+            }
+            b't' => bytes.get(i..)?.starts_with(b"true").then_some(i + 4),
+            b'f' => bytes.get(i..)?.starts_with(b"false").then_some(i + 5),
+            b'n' => bytes.get(i..)?.starts_with(b"null").then_some(i + 4),
+            _ => Self::json_skip_number(bytes, i),
+        }
+    }
+
+    /// End of the object starting at `i`; collects its members when asked to
+    fn json_skip_object<'a>(
+        bytes: &'a [u8],
+        i: usize,
+        depth: usize,
+        mut members: Option<&mut Vec<(String, &'a [u8])>>,
+    ) -> Option<usize> {
+        if bytes.get(i) != Some(&b'{') {
+            return None;
+        }
+        let depth = depth.checked_sub(1)?;
+        let mut keys: Vec<String> = Vec::new();
+        let mut i = Self::json_skip_ws(bytes, i + 1);
+        if bytes.get(i) == Some(&b'}') {
+            return Some(i + 1);
+        }
+        loop {
+            let (key, len) = Self::json_string(bytes.get(i..)?)?;
+            if keys.contains(&key) {
+                return None;
+            }
+            i = Self::json_skip_ws(bytes, i + len);
+            if bytes.get(i) != Some(&b':') {
+                return None;
+            }
+            let start = Self::json_skip_ws(bytes, i + 1);
+            let end = Self::json_skip_value(bytes, start, depth)?;
+            if let Some(members) = members.as_mut() {
+                members.push((key.clone(), bytes.get(start..end)?));
+            }
+            keys.push(key);
+            i = Self::json_skip_ws(bytes, end);
+            match bytes.get(i)? {
+                b',' => i = Self::json_skip_ws(bytes, i + 1),
+                b'}' => return Some(i + 1),
+                _ => return None,
+            }
+        }
+    }
+
+    /// End of the number starting at `i`: `-? (0 | [1-9][0-9]*) (.[0-9]+)? ([eE][+-]?[0-9]+)?`
+    fn json_skip_number(bytes: &[u8], mut i: usize) -> Option<usize> {
+        let digits = |i: usize| i + bytes.get(i..).map_or(0, |rest| rest.iter().take_while(|c| c.is_ascii_digit()).count());
+        if bytes.get(i) == Some(&b'-') {
+            i += 1;
+        }
+        i = match bytes.get(i)? {
+            b'0' => i + 1,
+            b'1'..=b'9' => digits(i),
+            _ => return None,
+        };
+        if bytes.get(i) == Some(&b'.') {
+            let end = digits(i + 1);
+            if end == i + 1 {
+                return None;
+            }
+            i = end;
+        }
+        if matches!(bytes.get(i), Some(b'e' | b'E')) {
+            i += 1;
+            if matches!(bytes.get(i), Some(b'+' | b'-')) {
+                i += 1;
+            }
+            let end = digits(i);
+            if end == i {
+                return None;
+            }
+            i = end;
+        }
+        Some(i)
+    }
+
+    /// Decode the string literal at the start of `bytes`; returns the value and the literal's length
+    fn json_string(bytes: &[u8]) -> Option<(String, usize)> {
+        if bytes.first() != Some(&b'"') {
+            return None;
+        }
+        let mut out = Vec::new();
+        let mut i = 1;
+        loop {
+            match *bytes.get(i)? {
+                b'"' => return String::from_utf8(out).ok().map(|value| (value, i + 1)),
+                b'\\' => {
+                    let escaped = match *bytes.get(i + 1)? {
+                        b'"' => 0x22,
+                        b'\\' => 0x5c,
+                        b'/' => 0x2f,
+                        b'b' => 0x08,
+                        b'f' => 0x0c,
+                        b'n' => 0x0a,
+                        b'r' => 0x0d,
+                        b't' => 0x09,
+                        b'u' => {
+                            let mut code = Self::json_hex4(bytes.get(i + 2..)?)?;
+                            i += 4;
+                            if (0xd800..0xdc00).contains(&code) {
+                                if bytes.get(i + 2..i + 4)? != b"\\u" {
+                                    return None;
+                                }
+                                let low = Self::json_hex4(bytes.get(i + 4..)?)?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return None;
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                                i += 6;
+                            }
+                            code
+                        }
+                        _ => return None,
+                    };
+                    Self::push_utf8(&mut out, escaped)?;
+                    i += 2;
+                }
+                c if c < 0x20 => return None,
+                c => {
+                    out.push(c);
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// Append the UTF-8 encoding of a code point; None for lone surrogates
+    fn push_utf8(out: &mut Vec<u8>, code: u32) -> Option<()> {
+        match code {
+            0..=0x7f => out.push(code as u8),
+            0x80..=0x7ff => out.extend_from_slice(&[0xc0 | (code >> 6) as u8, 0x80 | (code & 0x3f) as u8]),
+            0xd800..=0xdfff => return None,
+            0x800..=0xffff => out.extend_from_slice(&[
+                0xe0 | (code >> 12) as u8,
+                0x80 | ((code >> 6) & 0x3f) as u8,
+                0x80 | (code & 0x3f) as u8,
+            ]),
+            _ => out.extend_from_slice(&[
+                0xf0 | (code >> 18) as u8,
+                0x80 | ((code >> 12) & 0x3f) as u8,
+                0x80 | ((code >> 6) & 0x3f) as u8,
+                0x80 | (code & 0x3f) as u8,
+            ]),
+        }
+        Some(())
+    }
+
+    /// Value of the four hex digits at the start of `bytes`
+    fn json_hex4(bytes: &[u8]) -> Option<u32> {
+        bytes.get(..4)?.iter().try_fold(0u32, |code, c| Some(code * 16 + (*c as char).to_digit(16)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::{alloy_primitives::address, testing::*};
+
+    #[test]
+    fn test_cartesi_registration_requires_rollup_caller() {
+        let vm = TestVM::default();
+        let mut contract = CartesiGateway::from(&vm);
+        let admin_address = address!("0000000000000000000000000000000000000001");
+        let rollup_address = address!("0000000000000000000000000000000000000003");
+        let registry_address = address!("0000000000000000000000000000000000000004");
+        let owner_address = address!("0000000000000000000000000000000000000002");
+        let payload = br#"{"device_id":"did:lcore:rollup-device","did_document":"doc","owner_address":"0x0000000000000000000000000000000000000002","public_key":"04ab","proof":"0xc0ffee"}"#;
+
+        vm.set_sender(admin_address);
+        let _ = contract.initialize(registry_address);
+
+        // Untrusted callers are rejected
+        let result = contract.register_device_from_cartesi(payload.to_vec());
+        assert!(result.is_err(), "Untrusted caller should be rejected");
+
+        // Only the admin manages rollup callers
+        assert!(contract.set_rollup_caller(rollup_address, true).is_ok());
+        assert!(contract.is_rollup_caller(rollup_address).unwrap());
+
+        // Trusted rollups forward the decoded registration to the registry
+        vm.set_sender(rollup_address);
+        assert!(contract.set_rollup_caller(rollup_address, false).is_err());
+        let calldata = IDeviceRegistry::register_device_forCall {
+            owner: owner_address,
+            device_id: "did:lcore:rollup-device".into(),
+            did_document: "doc".into(),
+            public_key_hex: "04ab".into(),
+            device_type: "iot".into(),
+            metadata: "{}".into(),
+            proof: vec![0xc0, 0xff, 0xee].into(),
+        }
+        .abi_encode();
+        vm.mock_call(registry_address, calldata.clone(), Ok(Vec::new()));
+        let result = contract.register_device_from_cartesi(payload.to_vec());
+        assert!(result.is_ok(), "Trusted rollup should register device");
+
+        // Registry rejections are passed back to the rollup
+        vm.mock_call(registry_address, calldata, Err(b"Device already registered".to_vec()));
+        let result = contract.register_device_from_cartesi(payload.to_vec());
+        assert_eq!(result.unwrap_err(), b"Device already registered".to_vec());
+
+        vm.set_sender(admin_address);
+        assert!(contract.set_rollup_caller(rollup_address, false).is_ok());
+        assert!(!contract.is_rollup_caller(rollup_address).unwrap());
+    }
+
+    #[test]
+    fn test_cartesi_payload_decoding() {
+        let owner_address = address!("0000000000000000000000000000000000000002");
+
+        // ABI-encoded payload behind the version byte
+        let registration = CartesiSignedRegistration {
+            registration: CartesiDeviceRegistration {
+                device_id: "did:lcore:abi-device".into(),
+                did_document: r#"{"id":"did:lcore:abi-device"}"#.into(),
+                public_key: "04ab".into(),
+                device_type: "environmental_sensor".into(),
+                metadata: r#"{"site":"A"}"#.into(),
+                owner: owner_address,
+            },
+            proof: vec![1u8; 64].into(),
+        };
+        let mut payload = vec![PAYLOAD_VERSION_ABI];
+        payload.extend(registration.abi_encode());
+        let decoded = CartesiGateway::decode_cartesi_payload(&payload).unwrap();
+        assert_eq!(decoded.registration.device_id, "did:lcore:abi-device");
+        assert_eq!(decoded.registration.metadata, r#"{"site":"A"}"#);
+        assert_eq!(decoded.registration.owner, owner_address);
+        assert_eq!(decoded.proof, registration.proof);
+
+        // Legacy JSON with whitespace, escaped quotes, a nested DID document and nested metadata
+        let payload = br#"
+            { "device_type" : "say \"hi\"", "device_id": "did:lcore:json-device",
+            "did_document": {"id": "did:lcore:json-device"},
+            "metadata": {"site": ["A", -1.5e3, 0, null, true]},
+            "owner_address": "0x0000000000000000000000000000000000000002", "proof": "0102" }"#;
+        let CartesiSignedRegistration { registration: decoded, proof } =
+            CartesiGateway::decode_cartesi_payload(payload).unwrap();
+        assert_eq!(decoded.device_id, "did:lcore:json-device");
+        assert_eq!(decoded.device_type, "say \"hi\"");
+        assert_eq!(decoded.did_document, r#"{"id": "did:lcore:json-device"}"#);
+        assert_eq!(decoded.metadata, r#"{"site": ["A", -1.5e3, 0, null, true]}"#);
+        assert_eq!(decoded.owner, owner_address);
+        assert_eq!(proof.to_vec(), vec![1, 2]);
+
+        // String metadata is unquoted and missing metadata defaults to an empty object
+        let legacy = |fields: &str| {
+            format!(
+                r#"{{"device_id":"d","did_document":"d","owner_address":"{}","proof":"0x01"{}}}"#,
+                owner_address, fields
+            )
+        };
+        let decoded = CartesiGateway::decode_cartesi_payload(legacy(r#","metadata":"{\"site\":\"B\"}""#).as_bytes());
+        assert_eq!(decoded.unwrap().registration.metadata, r#"{"site":"B"}"#);
+        let decoded = CartesiGateway::decode_cartesi_payload(legacy("").as_bytes());
+        assert_eq!(decoded.unwrap().registration.metadata, "{}");
+
+        // Legacy registrations must carry a hex proof of possession
+        let unsigned = legacy("").replace(r#","proof":"0x01""#, "");
+        assert!(CartesiGateway::decode_cartesi_payload(unsigned.as_bytes()).is_err());
+        let bad_proof = legacy("").replace("0x01", "0xzz");
+        assert!(CartesiGateway::decode_cartesi_payload(bad_proof.as_bytes()).is_err());
+
+        // Malformed JSON, non-string fields and unknown versions are rejected
+        assert!(CartesiGateway::decode_cartesi_payload(br#"{"device_id":"x""#).is_err());
+        assert!(CartesiGateway::decode_cartesi_payload(legacy(",}").as_bytes()).is_err());
+        assert!(CartesiGateway::decode_cartesi_payload(legacy(r#","metadata":{"a":tru}"#).as_bytes()).is_err());
+        assert!(CartesiGateway::decode_cartesi_payload(legacy(r#","device_type":7"#).as_bytes()).is_err());
+        assert!(CartesiGateway::decode_cartesi_payload(&[0x02, 0x00]).is_err());
+        assert!(CartesiGateway::decode_cartesi_payload(b"  ").is_err());
+
+        // A duplicated field is rejected instead of silently taking one of the values
+        let duplicated = legacy(r#","device_id":"other""#);
+        assert!(CartesiGateway::decode_cartesi_payload(duplicated.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_minimal_json() {
+        let members = CartesiGateway::json_object_members(
+            br#" {"id":"did:x:\u00e9\ud83d\ude00\/", "n": [1, {"k": -2}]} "#,
+        )
+        .unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(CartesiGateway::json_string(members[0].1).unwrap().0, "did:x:\u{e9}\u{1f600}/");
+        assert_eq!(members[1].1, br#"[1, {"k": -2}]"#);
+
+        assert!(CartesiGateway::json_object_members(b"[1]").is_none());
+        assert!(CartesiGateway::json_object_members(br#"{"a":1} x"#).is_none());
+        assert!(CartesiGateway::json_object_members(br#"{"a" 1}"#).is_none());
+        assert!(CartesiGateway::json_object_members(br#"{"a":"\ud83d"}"#).is_none());
+        assert!(CartesiGateway::json_object_members(b"{\"a\":\"\x01\"}").is_none());
+        assert!(CartesiGateway::json_object_members(&[b'"'; 1]).is_none());
+
+        // Duplicate keys are rejected at every nesting level
+        assert!(CartesiGateway::json_object_members(br#"{"a":1,"a":2}"#).is_none());
+        assert!(CartesiGateway::json_object_members(br#"{"a":{"b":1,"b":1}}"#).is_none());
+        assert!(CartesiGateway::json_object_members(br#"{"a":{"b":1},"b":{"b":1}}"#).is_some());
+
+        // Only RFC 8259 numbers are accepted
+        for number in ["0", "-0", "7", "-12", "0.5", "1.25e3", "1E-2", "2e+10"] {
+            let text = format!(r#"{{"n":{}}}"#, number);
+            assert!(CartesiGateway::json_object_members(text.as_bytes()).is_some(), "{}", number);
+        }
+        for number in ["-", "01", "-01", "1.2.3", "1.", ".5", "+1", "1e", "1e+", "0x10", "1-2", "Infinity"] {
+            let text = format!(r#"{{"n":{}}}"#, number);
+            assert!(CartesiGateway::json_object_members(text.as_bytes()).is_none(), "{}", number);
+        }
+
+        // Nesting is bounded
+        let deep = format!(r#"{{"a":{}{}}}"#, "[".repeat(40), "]".repeat(40));
+        assert!(CartesiGateway::json_object_members(deep.as_bytes()).is_none());
+    }
+}
@@ -0,0 +1,87 @@
+#!/bin/bash
+
+# L{CORE} Stylus Contracts Check Script
+# Runs `cargo stylus check` on every contract and fails if any of them would not
+# deploy: Stylus only activates programs that fit in 24 KB brotli-compressed,
+# in a single fragment
+
+# Colors for output
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+YELLOW='\033[1;33m'
+BLUE='\033[0;34m'
+NC='\033[0m' # No Color
+
+# Configuration
+RPC_URL="${RPC_URL:-https://rpc.kc-chain.dev}"
+MAX_CONTRACT_SIZE=24576
+# Set OFFLINE=1 to accept a contract whose size passes when the RPC endpoint is unreachable
+OFFLINE="${OFFLINE:-0}"
+
+CONTRACTS=(
+    "device_registry"
+    "iot_data_pipeline"
+    "cartesi_gateway"
+    "device_keys"
+    "p256_verifier"
+    "device_operators"
+    "device_access"
+    "access_broker"
+)
+
+SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
+FAILED=0
+
+echo -e "${BLUE}🔍 L{CORE} Stylus Contracts Check${NC}"
+echo -e "${BLUE}=================================${NC}"
+
+# Check a single contract; returns non-zero if it cannot be deployed
+check_contract() {
+    local CONTRACT_DIR=$1
+
+    echo -e "\n${BLUE}📦 Checking $CONTRACT_DIR...${NC}"
+
+    local OUTPUT
+    OUTPUT=$(cd "$SCRIPT_DIR/$CONTRACT_DIR" && cargo stylus check --endpoint "$RPC_URL" 2>&1)
+    local STATUS=$?
+    OUTPUT=$(echo "$OUTPUT" | sed 's/\x1b\[[0-9;]*m//g')
+
+    local SIZE_LINE
+    SIZE_LINE=$(echo "$OUTPUT" | grep -o 'contract size: .*' | tail -1)
+    if [ -z "$SIZE_LINE" ]; then
+        echo -e "${RED}❌ $CONTRACT_DIR did not build${NC}"
+        echo "$OUTPUT" | tail -20
+        return 1
+    fi
+    echo -e "${YELLOW}   $SIZE_LINE${NC}"
+
+    local SIZE
+    SIZE=$(echo "$SIZE_LINE" | grep -oE '\([0-9]+ bytes\)' | grep -oE '[0-9]+')
+    if echo "$SIZE_LINE" | grep -q "fragments" || [ "$SIZE" -gt "$MAX_CONTRACT_SIZE" ]; then
+        echo -e "${RED}❌ $CONTRACT_DIR exceeds the $MAX_CONTRACT_SIZE byte contract size limit${NC}"
+        return 1
+    fi
+
+    if [ "$STATUS" -ne 0 ]; then
+        if [ "$OFFLINE" = "1" ] && echo "$OUTPUT" | grep -qE "error sending request|tcp connect error"; then
+            echo -e "${YELLOW}⚠️ $CONTRACT_DIR size OK; activation check skipped ($RPC_URL unreachable)${NC}"
+            return 0
+        fi
+        echo -e "${RED}❌ cargo stylus check failed for $CONTRACT_DIR${NC}"
+        echo "$OUTPUT" | tail -20
+        return 1
+    fi
+
+    echo -e "${GREEN}✅ $CONTRACT_DIR passed${NC}"
+}
+
+for CONTRACT in "${CONTRACTS[@]}"; do
+    check_contract "$CONTRACT" || FAILED=1
+done
+
+if [ "$FAILED" -ne 0 ]; then
+    echo -e "\n${RED}❌ Contract checks failed${NC}"
+    exit 1
+fi
+
+echo -e "\n${GREEN}🎉 All contracts passed${NC}"
@@ -109,9 +109,27 @@ deploy_contract "DeviceRegistry" "device_registry"
 # Deploy IoTDataPipeline  
 deploy_contract "IoTDataPipeline" "iot_data_pipeline"
 
+# Deploy CartesiGateway (register it as a DeviceRegistry rollup caller after initializing)
+deploy_contract "CartesiGateway" "cartesi_gateway"
+
+# Deploy DeviceKeys (initialize it with the DeviceRegistry address)
+deploy_contract "DeviceKeys" "device_keys"
+
+# Deploy P256Verifier (set it as the DeviceKeys fallback verifier on chains without RIP-7212)
+deploy_contract "P256Verifier" "p256_verifier"
+
+# Deploy DeviceOperators (initialize it with the DeviceRegistry address and set it on DeviceRegistry)
+deploy_contract "DeviceOperators" "device_operators"
+
+# Deploy DeviceAccess (initialize it with the DeviceRegistry address and set it on IoTDataPipeline)
+deploy_contract "DeviceAccess" "device_access"
+
+# Deploy AccessBroker (initialize it with the DeviceAccess address and set it as a DeviceAccess grant delegate)
+deploy_contract "AccessBroker" "access_broker"
+
 echo -e "\n${GREEN}🎉 Deployment Complete!${NC}"
 echo -e "${GREEN}========================${NC}"
-echo -e "${GREEN}✅ Both contracts deployed successfully${NC}"
+echo -e "${GREEN}✅ All contracts deployed successfully${NC}"
 echo -e "${GREEN}✅ ABI files generated${NC}"
 echo -e "${GREEN}✅ Ready for integration${NC}"
 
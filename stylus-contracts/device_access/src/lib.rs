@@ -0,0 +1,1199 @@
+// SPDX-License-Identifier: UNLICENSED
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+// Keep `core::fmt` panic formatting out of the deployed code
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall, SolEvent, SolValue};
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256, U8},
+    prelude::*,
+};
+
+// Data marketplace access grants for devices in the DeviceRegistry, either owner-wide
+// (every device the owner holds) or scoped to a single device
+sol_storage! {
+    #[entrypoint]
+    pub struct DeviceAccess {
+        /// Admin and configuration
+        address admin;
+        address device_registry_address;
+
+        /// Owner-wide grants
+        mapping(address => mapping(address => bool)) access_permissions; // owner -> consumer -> allowed
+        mapping(address => mapping(address => uint256)) permission_expires; // owner -> consumer -> expiry timestamp
+
+        /// Device-scoped grants (device_id_hash -> consumer -> grant)
+        mapping(bytes32 => mapping(address => DeviceGrant)) device_grants;
+
+        /// Owner-wide scoped grants, each scope bit with its own expiry
+        mapping(address => mapping(address => uint8)) permission_scopes; // owner -> consumer -> ACCESS_SCOPE_* bitmask
+        mapping(address => mapping(address => mapping(uint8 => uint256))) scope_expires; // owner -> consumer -> scope bit -> expiry
+
+        /// Trusted contracts that grant on owners' behalf once they have authorized it
+        /// themselves (AccessBroker permits)
+        mapping(address => bool) grant_delegates;
+
+        /// Consumers holding an owner-wide (full or scoped) grant, for enumeration
+        mapping(address => address[]) owner_consumers; // owner -> consumers
+        mapping(address => mapping(address => uint256)) consumer_index; // owner -> consumer -> index + 1 (0 = absent)
+
+        /// Start of each owner-wide grant's window
+        mapping(address => mapping(address => uint256)) permission_not_before; // owner -> consumer -> start timestamp
+    }
+
+    /// Device-scoped grant, honoured only while `granted_by` still owns the device and
+    /// the device has not changed hands since (`owner_epoch`)
+    pub struct DeviceGrant {
+        address granted_by;
+        uint256 owner_epoch;
+        uint256 expires_at; // ACCESS_PERMANENT = never expires
+        uint8 scopes;       // ACCESS_SCOPE_* bitmask
+    }
+}
+
+/// Access scope bits: data buyers may hold any combination
+const ACCESS_SCOPE_RAW: u8 = 1 << 0; // Raw device readings
+const ACCESS_SCOPE_AGGREGATED: u8 = 1 << 1; // Aggregates computed in Cartesi
+const ACCESS_SCOPE_METADATA: u8 = 1 << 2; // Device metadata only
+const ACCESS_SCOPE_ALL: u8 = ACCESS_SCOPE_RAW | ACCESS_SCOPE_AGGREGATED | ACCESS_SCOPE_METADATA;
+
+/// Stored expiry of every permanent grant (callers pass `expires_at = 0` to request it,
+/// and getters and events report it as 0)
+const ACCESS_PERMANENT: U256 = U256::MAX;
+
+/// Consumer page returned by `get_consumers`: (consumers, expiries, scope bitmasks)
+type ConsumerPage = (Vec<Address>, Vec<U256>, Vec<u8>);
+
+sol! {
+    event AccessGranted(
+        address indexed owner,
+        address indexed consumer,
+        uint256 expires_at
+    );
+
+    event AccessRevoked(
+        address indexed owner,
+        address indexed consumer
+    );
+
+    event AccessScopeGranted(
+        address indexed owner,
+        address indexed consumer,
+        uint8 scopes,
+        uint256 expires_at
+    );
+
+    event AccessScopeRevoked(
+        address indexed owner,
+        address indexed consumer,
+        uint8 scopes
+    );
+
+    event GrantDelegateUpdated(
+        address indexed delegate,
+        bool trusted
+    );
+
+    event DeviceAccessGranted(
+        bytes32 indexed device_id_hash,
+        address indexed owner,
+        address indexed consumer,
+        uint8 scopes,
+        uint256 expires_at
+    );
+
+    event DeviceAccessRevoked(
+        bytes32 indexed device_id_hash,
+        address indexed owner,
+        address indexed consumer
+    );
+}
+
+// Interface for DeviceRegistry contract calls
+sol! {
+    interface IDeviceRegistry {
+        function get_device_owner(bytes32 device_id_hash) external view returns (address);
+        function get_device_owner_epoch(bytes32 device_id_hash) external view returns (uint256);
+        function is_device_manager(bytes32 device_id_hash, address account) external view returns (bool);
+        function device_operators() external view returns (address);
+    }
+
+    interface IDeviceOperators {
+        function is_approved_for_all(address owner, address operator) external view returns (bool);
+    }
+}
+
+#[public]
+impl DeviceAccess {
+    /// Initialize the contract with the DeviceRegistry whose devices it grants access to
+    pub fn initialize(&mut self, device_registry: Address) -> Result<(), Vec<u8>> {
+        if self.admin.get() != Address::ZERO {
+            return Err(b"Already initialized".to_vec());
+        }
+        self.admin.set(self.vm().msg_sender());
+        self.device_registry_address.set(device_registry);
+        Ok(())
+    }
+
+    // ========== Owner-wide Grants ==========
+
+    /// Grant data access to all of the caller's devices to a consumer, effective immediately
+    /// `expires_at` = 0 grants permanent access
+    pub fn grant_access(&mut self, consumer: Address, expires_at: U256) -> Result<(), Vec<u8>> {
+        self.grant_access_window(consumer, U256::ZERO, expires_at)
+    }
+
+    /// Grant data access to all of the caller's devices to a consumer for `[not_before, expires_at]`
+    /// Any previous window is replaced; `expires_at` = 0 grants permanent access
+    pub fn grant_access_window(&mut self, consumer: Address, not_before: U256, expires_at: U256) -> Result<(), Vec<u8>> {
+        if consumer == Address::ZERO {
+            return Err(b"Invalid consumer address".to_vec());
+        }
+
+        let owner = self.vm().msg_sender();
+        self.record_access_grant(owner, consumer, not_before, expires_at)
+    }
+
+    /// Revoke data access from a consumer (called by device owner)
+    pub fn revoke_access(&mut self, consumer: Address) -> Result<(), Vec<u8>> {
+        let owner = self.vm().msg_sender();
+        self.clear_access_grant(owner, consumer);
+        Ok(())
+    }
+
+    /// Grant data access to all of `owner`'s devices on its behalf (called by owner, operator or grant delegate)
+    pub fn grant_access_for(&mut self, owner: Address, consumer: Address, expires_at: U256) -> Result<(), Vec<u8>> {
+        self.only_acting_for(owner)?;
+        if consumer == Address::ZERO {
+            return Err(b"Invalid consumer address".to_vec());
+        }
+        self.record_access_grant(owner, consumer, U256::ZERO, expires_at)
+    }
+
+    /// Revoke data access to `owner`'s devices on its behalf (called by owner, operator or grant delegate)
+    pub fn revoke_access_for(&mut self, owner: Address, consumer: Address) -> Result<(), Vec<u8>> {
+        self.only_acting_for(owner)?;
+        self.clear_access_grant(owner, consumer);
+        Ok(())
+    }
+
+    /// Check if consumer has access to all of an owner's devices
+    pub fn has_access(&self, owner: Address, consumer: Address) -> Result<bool, Vec<u8>> {
+        if !self.access_permissions.getter(owner).getter(consumer).get() {
+            return Ok(false);
+        }
+
+        // Check grant window
+        let not_before = self.permission_not_before.getter(owner).getter(consumer).get();
+        let expires_at = self.permission_expires.getter(owner).getter(consumer).get();
+        Ok(Self::access_window_open(U256::from(self.vm().block_timestamp()), not_before, expires_at))
+    }
+
+    /// Get a consumer's owner-wide grant: (granted, not_before, expires_at), expires_at = 0 if permanent
+    pub fn get_access_window(&self, owner: Address, consumer: Address) -> Result<(bool, U256, U256), Vec<u8>> {
+        Ok((
+            self.access_permissions.getter(owner).getter(consumer).get(),
+            self.permission_not_before.getter(owner).getter(consumer).get(),
+            Self::reported_expiry(self.permission_expires.getter(owner).getter(consumer).get()),
+        ))
+    }
+
+    /// Grant scoped data access to all of the caller's devices to a consumer
+    /// Each scope bit in `scopes` gets its own expiry, replacing the previous one (0 = never expires)
+    pub fn grant_access_scope(&mut self, consumer: Address, scopes: u8, expires_at: U256) -> Result<(), Vec<u8>> {
+        if consumer == Address::ZERO {
+            return Err(b"Invalid consumer address".to_vec());
+        }
+        Self::check_scope(scopes)?;
+
+        let owner = self.vm().msg_sender();
+        self.record_scope_grant(owner, consumer, scopes, expires_at)
+    }
+
+    /// Revoke scoped data access from a consumer (called by device owner)
+    pub fn revoke_access_scope(&mut self, consumer: Address, scopes: u8) -> Result<(), Vec<u8>> {
+        Self::check_scope(scopes)?;
+
+        let owner = self.vm().msg_sender();
+        self.clear_scope_grant(owner, consumer, scopes);
+        Ok(())
+    }
+
+    /// Grant scoped data access to all of `owner`'s devices on its behalf (called by owner, operator or grant delegate)
+    pub fn grant_access_scope_for(
+        &mut self,
+        owner: Address,
+        consumer: Address,
+        scopes: u8,
+        expires_at: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.only_acting_for(owner)?;
+        if consumer == Address::ZERO {
+            return Err(b"Invalid consumer address".to_vec());
+        }
+        Self::check_scope(scopes)?;
+        self.record_scope_grant(owner, consumer, scopes, expires_at)
+    }
+
+    /// Revoke scoped data access to `owner`'s devices on its behalf (called by owner, operator or grant delegate)
+    pub fn revoke_access_scope_for(&mut self, owner: Address, consumer: Address, scopes: u8) -> Result<(), Vec<u8>> {
+        self.only_acting_for(owner)?;
+        Self::check_scope(scopes)?;
+        self.clear_scope_grant(owner, consumer, scopes);
+        Ok(())
+    }
+
+    /// Check if consumer holds every bit of `scope` on all of an owner's devices;
+    /// full grants cover every scope
+    pub fn has_access_scope(&self, owner: Address, consumer: Address, scope: u8) -> Result<bool, Vec<u8>> {
+        Self::check_scope(scope)?;
+        if self.has_access(owner, consumer)? {
+            return Ok(true);
+        }
+
+        let granted = self.permission_scopes.getter(owner).getter(consumer).get().to::<u8>();
+        if granted & scope != scope {
+            return Ok(false);
+        }
+
+        let now = U256::from(self.vm().block_timestamp());
+        let owner_expires = self.scope_expires.getter(owner);
+        let consumer_expires = owner_expires.getter(consumer);
+        for bit in Self::scope_bits(scope) {
+            if !Self::access_window_open(now, U256::ZERO, consumer_expires.getter(U8::from(bit)).get()) {
+                return Ok(false); // Scope expired
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Get the scope bitmask granted to a consumer owner-wide (ignores expiry)
+    pub fn get_access_scopes(&self, owner: Address, consumer: Address) -> Result<u8, Vec<u8>> {
+        Ok(self.permission_scopes.getter(owner).getter(consumer).get().to::<u8>())
+    }
+
+    /// Get size of an owner's consumer set: owner-wide grantees, including lapsed grants not yet revoked
+    pub fn get_consumer_count(&self, owner: Address) -> Result<U256, Vec<u8>> {
+        Ok(U256::from(self.owner_consumers.getter(owner).len()))
+    }
+
+    /// Get consumers holding an active owner-wide (full or scoped) grant from an owner (paginated),
+    /// with their expiry and active scope bitmask; device grants are not listed
+    /// Expiry is that of the full grant, or else the latest expiry among active scopes (0 = never)
+    /// Pages index the consumer set and skip lapsed grants, so a page may hold fewer than `limit` entries
+    pub fn get_consumers(&self, owner: Address, offset: U256, limit: U256) -> Result<ConsumerPage, Vec<u8>> {
+        let consumers = self.owner_consumers.getter(owner);
+        let (start, end) = Self::page_bounds(consumers.len(), offset, limit);
+
+        let mut addresses = Vec::with_capacity(end - start);
+        let mut expiries = Vec::with_capacity(end - start);
+        let mut scopes = Vec::with_capacity(end - start);
+        for i in start..end {
+            if let Some(consumer) = consumers.get(i) {
+                let (expires_at, scope) = self.consumer_grant_summary(owner, consumer);
+                if scope == 0 {
+                    continue;
+                }
+                addresses.push(consumer);
+                expiries.push(expires_at);
+                scopes.push(scope);
+            }
+        }
+        Ok((addresses, expiries, scopes))
+    }
+
+    // ========== Device Grants ==========
+
+    /// Grant full data access to a single device to a consumer (called by device owner or operator)
+    pub fn grant_device_access(&mut self, device_id_hash: B256, consumer: Address, expires_at: U256) -> Result<(), Vec<u8>> {
+        self.grant_device_access_scope(device_id_hash, consumer, ACCESS_SCOPE_ALL, expires_at)
+    }
+
+    /// Grant a single device's data to a consumer for the scope bits in `scopes` only
+    /// (called by device owner or operator); replaces any earlier grant for the device, and lapses
+    /// when the device is transferred
+    pub fn grant_device_access_scope(
+        &mut self,
+        device_id_hash: B256,
+        consumer: Address,
+        scopes: u8,
+        expires_at: U256,
+    ) -> Result<(), Vec<u8>> {
+        if consumer == Address::ZERO {
+            return Err(b"Invalid consumer address".to_vec());
+        }
+        Self::check_scope(scopes)?;
+        let owner = self.only_device_manager(device_id_hash)?;
+        self.record_device_grant(device_id_hash, owner, consumer, scopes, expires_at)
+    }
+
+    /// Grant a single device's data to a consumer on its owner's behalf (called by owner,
+    /// operator or grant delegate); fails unless `owner` still owns the device
+    pub fn grant_device_access_scope_for(
+        &mut self,
+        device_id_hash: B256,
+        owner: Address,
+        consumer: Address,
+        scopes: u8,
+        expires_at: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.only_acting_for(owner)?;
+        if consumer == Address::ZERO {
+            return Err(b"Invalid consumer address".to_vec());
+        }
+        Self::check_scope(scopes)?;
+        let current_owner = self.registry_device_owner(device_id_hash)?;
+        if current_owner == Address::ZERO {
+            return Err(b"Device not registered".to_vec());
+        }
+        if current_owner != owner {
+            return Err(b"Device not owned by owner".to_vec());
+        }
+        self.record_device_grant(device_id_hash, owner, consumer, scopes, expires_at)
+    }
+
+    /// Revoke a consumer's access to a single device (called by device owner or operator)
+    pub fn revoke_device_access(&mut self, device_id_hash: B256, consumer: Address) -> Result<(), Vec<u8>> {
+        let owner = self.only_device_manager(device_id_hash)?;
+
+        let mut grants = self.device_grants.setter(device_id_hash);
+        let mut grant = grants.setter(consumer);
+        grant.granted_by.set(Address::ZERO);
+        grant.owner_epoch.set(U256::ZERO);
+        grant.expires_at.set(U256::ZERO);
+        grant.scopes.set(U8::ZERO);
+
+        self.emit(DeviceAccessRevoked {
+            device_id_hash,
+            owner,
+            consumer,
+        });
+
+        Ok(())
+    }
+
+    /// Check if consumer has full access to a device's data, via a grant for the device
+    /// or an owner-wide grant from its current owner
+    pub fn has_device_access(&self, device_id_hash: B256, consumer: Address) -> Result<bool, Vec<u8>> {
+        self.has_device_access_scope(device_id_hash, consumer, ACCESS_SCOPE_ALL)
+    }
+
+    /// Check if consumer holds every bit of `scope` on a device's data, via a grant for
+    /// the device or an owner-wide grant from its current owner
+    pub fn has_device_access_scope(&self, device_id_hash: B256, consumer: Address, scope: u8) -> Result<bool, Vec<u8>> {
+        Self::check_scope(scope)?;
+        let owner = self.registry_device_owner(device_id_hash)?;
+        if owner == Address::ZERO {
+            return Ok(false);
+        }
+
+        // Grants made before the device last changed hands have lapsed
+        let grants = self.device_grants.getter(device_id_hash);
+        let grant = grants.getter(consumer);
+        if grant.granted_by.get() == owner
+            && grant.scopes.get().to::<u8>() & scope == scope
+            && grant.owner_epoch.get() == self.registry_device_owner_epoch(device_id_hash)?
+        {
+            let now = U256::from(self.vm().block_timestamp());
+            if Self::access_window_open(now, U256::ZERO, grant.expires_at.get()) {
+                return Ok(true);
+            }
+        }
+
+        self.has_access_scope(owner, consumer, scope)
+    }
+
+    /// Get a device grant: (granted_by, scopes, expires_at), zero if the consumer holds none;
+    /// expires_at = 0 if permanent
+    pub fn get_device_grant(&self, device_id_hash: B256, consumer: Address) -> Result<(Address, u8, U256), Vec<u8>> {
+        let grants = self.device_grants.getter(device_id_hash);
+        let grant = grants.getter(consumer);
+        Ok((grant.granted_by.get(), grant.scopes.get().to::<u8>(), Self::reported_expiry(grant.expires_at.get())))
+    }
+
+    // ========== Admin Functions ==========
+
+    /// Get contract owner
+    pub fn owner(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.admin.get())
+    }
+
+    /// Get the DeviceRegistry whose devices grants apply to
+    pub fn device_registry(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.device_registry_address.get())
+    }
+
+    /// Update DeviceRegistry address
+    pub fn set_device_registry(&mut self, device_registry: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.device_registry_address.set(device_registry);
+        Ok(())
+    }
+
+    /// Add or remove a trusted grant delegate for the `*_for` functions
+    pub fn set_grant_delegate(&mut self, delegate: Address, trusted: bool) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if delegate == Address::ZERO {
+            return Err(b"Invalid delegate address".to_vec());
+        }
+        self.grant_delegates.setter(delegate).set(trusted);
+        self.emit(GrantDelegateUpdated { delegate, trusted });
+        Ok(())
+    }
+
+    /// Check if an address is a trusted grant delegate
+    pub fn is_grant_delegate(&self, delegate: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.grant_delegates.getter(delegate).get())
+    }
+}
+
+// Private helper functions
+impl DeviceAccess {
+    /// Emit an event; unlike `stylus_sdk::prelude::log` this cannot panic, which keeps
+    /// panic formatting out of the contract
+    fn emit<T: SolEvent>(&self, event: T) {
+        let mut topics = [alloy_sol_types::abi::token::WordToken::default(); 4];
+        let _ = event.encode_topics_raw(&mut topics);
+        let count = <T::TopicList as alloy_sol_types::TopicList>::COUNT;
+        let mut data = Vec::with_capacity(32 * count);
+        for topic in &topics[..count] {
+            data.extend_from_slice(topic.as_slice());
+        }
+        event.encode_data_to(&mut data);
+        self.vm().emit_log(&data, count);
+    }
+
+    /// Ensure only contract owner can call
+    fn only_owner(&self) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != self.admin.get() {
+            return Err(b"Only owner can call this function".to_vec());
+        }
+        Ok(())
+    }
+
+    /// Ensure the caller is `owner`, an operator approved for all of its devices or a
+    /// trusted grant delegate
+    fn only_acting_for(&self, owner: Address) -> Result<(), Vec<u8>> {
+        let sender = self.vm().msg_sender();
+        if sender != owner && !self.grant_delegates.getter(sender).get() && !self.is_operator_for_all(owner, sender) {
+            return Err(b"Not authorized for owner".to_vec());
+        }
+        Ok(())
+    }
+
+    /// Check with the registry's DeviceOperators whether `operator` is approved for all of `owner`'s devices
+    fn is_operator_for_all(&self, owner: Address, operator: Address) -> bool {
+        let Ok(response) = self.registry_call(&IDeviceRegistry::device_operatorsCall {}.abi_encode()) else {
+            return false;
+        };
+        let Ok((device_operators,)) = <(Address,)>::abi_decode(&response, true) else {
+            return false;
+        };
+        let calldata = IDeviceOperators::is_approved_for_allCall { owner, operator }.abi_encode();
+        device_operators != Address::ZERO
+            && self
+                .vm()
+                .static_call(&self, device_operators, &calldata)
+                .is_ok_and(|response| response.len() == 32 && U256::from_be_slice(&response) == U256::from(1))
+    }
+
+    /// Record an owner-wide grant for `[not_before, expires_at]` and announce it
+    fn record_access_grant(
+        &mut self,
+        owner: Address,
+        consumer: Address,
+        not_before: U256,
+        expires_at: U256,
+    ) -> Result<(), Vec<u8>> {
+        let stored_expiry = self.stored_expiry(expires_at)?;
+        if not_before > stored_expiry {
+            return Err(b"Invalid access window".to_vec());
+        }
+
+        // Set permission, always replacing any previous window
+        self.access_permissions.setter(owner).setter(consumer).set(true);
+        self.permission_not_before.setter(owner).setter(consumer).set(not_before);
+        self.permission_expires.setter(owner).setter(consumer).set(stored_expiry);
+        self.sync_owner_consumer(owner, consumer);
+
+        self.emit(AccessGranted {
+            owner,
+            consumer,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Clear an owner-wide grant and announce it
+    fn clear_access_grant(&mut self, owner: Address, consumer: Address) {
+        self.access_permissions.setter(owner).setter(consumer).set(false);
+        self.permission_not_before.setter(owner).setter(consumer).set(U256::ZERO);
+        self.permission_expires.setter(owner).setter(consumer).set(U256::ZERO);
+        self.sync_owner_consumer(owner, consumer);
+
+        self.emit(AccessRevoked {
+            owner,
+            consumer,
+        });
+    }
+
+    /// Add scope bits to an owner-wide grant, each with `expires_at`, and announce it
+    fn record_scope_grant(&mut self, owner: Address, consumer: Address, scopes: u8, expires_at: U256) -> Result<(), Vec<u8>> {
+        let stored_expiry = self.stored_expiry(expires_at)?;
+        let granted = self.permission_scopes.getter(owner).getter(consumer).get().to::<u8>();
+        self.permission_scopes.setter(owner).setter(consumer).set(U8::from(granted | scopes));
+        let mut owner_expires = self.scope_expires.setter(owner);
+        let mut consumer_expires = owner_expires.setter(consumer);
+        for bit in Self::scope_bits(scopes) {
+            consumer_expires.setter(U8::from(bit)).set(stored_expiry);
+        }
+        self.sync_owner_consumer(owner, consumer);
+
+        self.emit(AccessScopeGranted {
+            owner,
+            consumer,
+            scopes,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Remove scope bits from an owner-wide grant and announce it
+    fn clear_scope_grant(&mut self, owner: Address, consumer: Address, scopes: u8) {
+        let granted = self.permission_scopes.getter(owner).getter(consumer).get().to::<u8>();
+        self.permission_scopes.setter(owner).setter(consumer).set(U8::from(granted & !scopes));
+        let mut owner_expires = self.scope_expires.setter(owner);
+        let mut consumer_expires = owner_expires.setter(consumer);
+        for bit in Self::scope_bits(scopes) {
+            consumer_expires.setter(U8::from(bit)).set(U256::ZERO);
+        }
+        self.sync_owner_consumer(owner, consumer);
+
+        self.emit(AccessScopeRevoked {
+            owner,
+            consumer,
+            scopes,
+        });
+    }
+
+    /// Add or prune a consumer in an owner's consumer set to match its current grants
+    fn sync_owner_consumer(&mut self, owner: Address, consumer: Address) {
+        let has_grant = self.access_permissions.getter(owner).getter(consumer).get()
+            || !self.permission_scopes.getter(owner).getter(consumer).get().is_zero();
+        let index = self.consumer_index.getter(owner).getter(consumer).get();
+
+        if has_grant && index.is_zero() {
+            let mut consumers = self.owner_consumers.setter(owner);
+            consumers.push(consumer);
+            let len = U256::from(consumers.len());
+            self.consumer_index.setter(owner).setter(consumer).set(len);
+        } else if !has_grant && !index.is_zero() {
+            // Swap-remove, keeping indices of the moved consumer up to date
+            let index = index.to::<usize>() - 1;
+            let mut consumers = self.owner_consumers.setter(owner);
+            let last = consumers.len() - 1;
+            if index != last {
+                let moved = consumers.get(last).unwrap_or_default();
+                if let Some(mut slot) = consumers.setter(index) {
+                    slot.set(moved);
+                }
+                self.consumer_index.setter(owner).setter(moved).set(U256::from(index + 1));
+            }
+            consumers.erase_last();
+            self.consumer_index.setter(owner).delete(consumer);
+        }
+    }
+
+    /// Effective (expiry, scope bitmask) of a consumer's active owner-wide grants (bitmask 0 if none)
+    fn consumer_grant_summary(&self, owner: Address, consumer: Address) -> (U256, u8) {
+        let now = U256::from(self.vm().block_timestamp());
+        if self.access_permissions.getter(owner).getter(consumer).get() {
+            let not_before = self.permission_not_before.getter(owner).getter(consumer).get();
+            let expires_at = self.permission_expires.getter(owner).getter(consumer).get();
+            if Self::access_window_open(now, not_before, expires_at) {
+                return (Self::reported_expiry(expires_at), ACCESS_SCOPE_ALL);
+            }
+        }
+
+        let scopes = self.permission_scopes.getter(owner).getter(consumer).get().to::<u8>();
+        let owner_expires = self.scope_expires.getter(owner);
+        let consumer_expires = owner_expires.getter(consumer);
+        let mut active = 0;
+        let mut latest = U256::ZERO;
+        for bit in Self::scope_bits(scopes) {
+            let expires_at = consumer_expires.getter(U8::from(bit)).get();
+            if Self::access_window_open(now, U256::ZERO, expires_at) {
+                active |= bit;
+                latest = latest.max(if expires_at.is_zero() { ACCESS_PERMANENT } else { expires_at });
+            }
+        }
+        (Self::reported_expiry(latest), active)
+    }
+
+    /// Clamp an (offset, limit) page request to `[start, end)` indices of a list of length `len`
+    fn page_bounds(len: usize, offset: U256, limit: U256) -> (usize, usize) {
+        let start = usize::try_from(offset).unwrap_or(usize::MAX).min(len);
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        (start, start.saturating_add(limit).min(len))
+    }
+
+    /// Record a device grant for the device's current ownership epoch and announce it
+    fn record_device_grant(
+        &mut self,
+        device_id_hash: B256,
+        owner: Address,
+        consumer: Address,
+        scopes: u8,
+        expires_at: U256,
+    ) -> Result<(), Vec<u8>> {
+        let stored_expiry = self.stored_expiry(expires_at)?;
+        let owner_epoch = self.registry_device_owner_epoch(device_id_hash)?;
+
+        // Scopes and expiry are always overwritten
+        let mut grants = self.device_grants.setter(device_id_hash);
+        let mut grant = grants.setter(consumer);
+        grant.granted_by.set(owner);
+        grant.owner_epoch.set(owner_epoch);
+        grant.expires_at.set(stored_expiry);
+        grant.scopes.set(U8::from(scopes));
+
+        self.emit(DeviceAccessGranted {
+            device_id_hash,
+            owner,
+            consumer,
+            scopes,
+            expires_at,
+        });
+
+        Ok(())
+    }
+
+    /// Map a requested expiry to its stored form (0 = ACCESS_PERMANENT), rejecting expiries in the past
+    fn stored_expiry(&self, expires_at: U256) -> Result<U256, Vec<u8>> {
+        if expires_at.is_zero() {
+            return Ok(ACCESS_PERMANENT);
+        }
+        if expires_at < U256::from(self.vm().block_timestamp()) {
+            return Err(b"Expiry in the past".to_vec());
+        }
+        Ok(expires_at)
+    }
+
+    /// Map a stored expiry to the form getters report (0 = never expires)
+    fn reported_expiry(expires_at: U256) -> U256 {
+        if expires_at == ACCESS_PERMANENT {
+            U256::ZERO
+        } else {
+            expires_at
+        }
+    }
+
+    /// Check whether `now` lies in a grant window; expiry is inclusive and
+    /// 0 (grants stored before explicit windows) never expires
+    fn access_window_open(now: U256, not_before: U256, expires_at: U256) -> bool {
+        now >= not_before && (expires_at.is_zero() || now <= expires_at)
+    }
+
+    /// Ensure `scope` is a non-empty combination of ACCESS_SCOPE_* bits
+    fn check_scope(scope: u8) -> Result<(), Vec<u8>> {
+        if scope == 0 || scope & !ACCESS_SCOPE_ALL != 0 {
+            return Err(b"Invalid access scope".to_vec());
+        }
+        Ok(())
+    }
+
+    /// Iterate over the individual bits set in `scopes`
+    fn scope_bits(scopes: u8) -> impl Iterator<Item = u8> {
+        (0..8).map(|i| 1u8 << i).filter(move |bit| scopes & bit != 0)
+    }
+
+    /// Ensure the caller owns or is an approved operator of a registered device, returning the owner
+    fn only_device_manager(&self, device_id_hash: B256) -> Result<Address, Vec<u8>> {
+        let owner = self.registry_device_owner(device_id_hash)?;
+        if owner == Address::ZERO {
+            return Err(b"Device not registered".to_vec());
+        }
+        let sender = self.vm().msg_sender();
+        if sender != owner && !self.registry_is_device_manager(device_id_hash, sender) {
+            return Err(b"Only device owner or operator can call this function".to_vec());
+        }
+        Ok(owner)
+    }
+
+    /// Check with the DeviceRegistry whether `account` owns or is an approved operator of a device
+    fn registry_is_device_manager(&self, device_id_hash: B256, account: Address) -> bool {
+        let calldata = IDeviceRegistry::is_device_managerCall { device_id_hash, account }.abi_encode();
+        self.registry_call(&calldata)
+            .is_ok_and(|response| response.len() == 32 && U256::from_be_slice(&response) == U256::from(1))
+    }
+
+    /// Static call into the DeviceRegistry
+    fn registry_call(&self, calldata: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+        let registry = self.device_registry_address.get();
+        Ok(self.vm().static_call(&self, registry, calldata)?)
+    }
+
+    /// Get device owner from the DeviceRegistry (zero if unregistered)
+    fn registry_device_owner(&self, device_id_hash: B256) -> Result<Address, Vec<u8>> {
+        let response = self.registry_call(&IDeviceRegistry::get_device_ownerCall { device_id_hash }.abi_encode())?;
+        let (owner,) = <(Address,)>::abi_decode(&response, true)
+            .map_err(|_| b"Failed to decode owner response".to_vec())?;
+        Ok(owner)
+    }
+
+    /// Get the number of times a device has changed owner from the DeviceRegistry
+    fn registry_device_owner_epoch(&self, device_id_hash: B256) -> Result<U256, Vec<u8>> {
+        let response =
+            self.registry_call(&IDeviceRegistry::get_device_owner_epochCall { device_id_hash }.abi_encode())?;
+        let (epoch,) = <(U256,)>::abi_decode(&response, true)
+            .map_err(|_| b"Failed to decode epoch response".to_vec())?;
+        Ok(epoch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::{alloy_primitives::address, crypto, testing::*};
+
+    const ADMIN: Address = address!("0000000000000000000000000000000000000001");
+    const OWNER: Address = address!("0000000000000000000000000000000000000002");
+    const CONSUMER: Address = address!("0000000000000000000000000000000000000003");
+    const REGISTRY: Address = address!("0000000000000000000000000000000000000004");
+
+    /// Run `f` with `sender` as msg.sender
+    fn with_sender<R>(vm: &TestVM, sender: Address, f: impl FnOnce() -> R) -> R {
+        vm.set_sender(sender);
+        f()
+    }
+
+    /// Mock the DeviceRegistry owner and ownership epoch of a device
+    fn mock_device_owner(vm: &TestVM, device_id_hash: B256, owner: Address, epoch: u64) {
+        vm.mock_static_call(
+            REGISTRY,
+            IDeviceRegistry::get_device_ownerCall { device_id_hash }.abi_encode(),
+            Ok(owner.abi_encode()),
+        );
+        vm.mock_static_call(
+            REGISTRY,
+            IDeviceRegistry::get_device_owner_epochCall { device_id_hash }.abi_encode(),
+            Ok(U256::from(epoch).abi_encode()),
+        );
+    }
+
+    fn setup(vm: &TestVM) -> DeviceAccess {
+        let mut contract = DeviceAccess::from(vm);
+        with_sender(vm, ADMIN, || assert!(contract.initialize(REGISTRY).is_ok()));
+        contract
+    }
+
+    #[test]
+    fn test_access_control() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+
+        with_sender(&vm, OWNER, || {
+            // Grant access
+            let result = contract.grant_access(CONSUMER, U256::ZERO);
+            assert!(result.is_ok(), "Access grant should succeed");
+
+            // Check access
+            assert!(contract.has_access(OWNER, CONSUMER).unwrap());
+
+            // Revoke access
+            let result = contract.revoke_access(CONSUMER);
+            assert!(result.is_ok(), "Access revoke should succeed");
+
+            // Check access revoked
+            assert!(!contract.has_access(OWNER, CONSUMER).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_permanent_regrant_after_expiry() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        vm.set_block_timestamp(1_000);
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.grant_access(CONSUMER, U256::from(1_500)).is_ok());
+            assert!(contract.has_access(OWNER, CONSUMER).unwrap());
+
+            // The finite grant lapses
+            vm.set_block_timestamp(2_000);
+            assert!(!contract.has_access(OWNER, CONSUMER).unwrap());
+
+            // Re-granting forever replaces the stale expiry
+            assert!(contract.grant_access(CONSUMER, U256::ZERO).is_ok());
+            assert!(contract.has_access(OWNER, CONSUMER).unwrap());
+            assert_eq!(contract.get_access_window(OWNER, CONSUMER).unwrap(), (true, U256::ZERO, U256::ZERO));
+            vm.set_block_timestamp(u64::MAX);
+            assert!(contract.has_access(OWNER, CONSUMER).unwrap());
+            vm.set_block_timestamp(2_000);
+
+            // Extending to a finite expiry replaces the permanent grant
+            assert!(contract.grant_access(CONSUMER, U256::from(3_000)).is_ok());
+            assert_eq!(contract.get_access_window(OWNER, CONSUMER).unwrap(), (true, U256::ZERO, U256::from(3_000)));
+            vm.set_block_timestamp(3_000);
+            assert!(contract.has_access(OWNER, CONSUMER).unwrap());
+            vm.set_block_timestamp(3_001);
+            assert!(!contract.has_access(OWNER, CONSUMER).unwrap());
+
+            // Windows open at not_before
+            assert!(contract.grant_access_window(CONSUMER, U256::from(4_000), U256::from(5_000)).is_ok());
+            assert!(!contract.has_access(OWNER, CONSUMER).unwrap());
+            vm.set_block_timestamp(4_000);
+            assert!(contract.has_access(OWNER, CONSUMER).unwrap());
+
+            // Expiries in the past and inverted windows are rejected on every grant path
+            assert!(contract.grant_access(CONSUMER, U256::from(3_999)).is_err());
+            assert!(contract.grant_access_window(CONSUMER, U256::from(6_000), U256::from(5_000)).is_err());
+            assert!(contract.grant_access_scope(CONSUMER, ACCESS_SCOPE_RAW, U256::from(3_999)).is_err());
+            assert!(contract.grant_access_for(OWNER, CONSUMER, U256::from(3_999)).is_err());
+            assert_eq!(contract.get_access_window(OWNER, CONSUMER).unwrap(), (true, U256::from(4_000), U256::from(5_000)));
+        });
+
+        let meter = crypto::keccak(b"did:lcore:meter");
+        mock_device_owner(&vm, meter, OWNER, 0);
+        with_sender(&vm, OWNER, || {
+            assert!(contract.grant_device_access(meter, CONSUMER, U256::from(3_999)).is_err());
+            assert!(contract.grant_device_access(meter, CONSUMER, U256::from(4_000)).is_ok());
+        });
+
+        // Revoking clears the window
+        with_sender(&vm, OWNER, || assert!(contract.revoke_access(CONSUMER).is_ok()));
+        assert_eq!(contract.get_access_window(OWNER, CONSUMER).unwrap(), (false, U256::ZERO, U256::ZERO));
+    }
+
+    #[test]
+    fn test_access_window_boundaries() {
+        let open = |now: u64, not_before: u64, expires_at: U256| {
+            DeviceAccess::access_window_open(U256::from(now), U256::from(not_before), expires_at)
+        };
+
+        // Expiry is inclusive
+        assert!(open(100, 0, U256::from(100)));
+        assert!(!open(101, 0, U256::from(100)));
+
+        // Window opens at not_before
+        assert!(!open(49, 50, U256::from(100)));
+        assert!(open(50, 50, U256::from(100)));
+
+        // Permanent grants and grants stored before explicit windows never lapse
+        assert!(open(u64::MAX, 0, ACCESS_PERMANENT));
+        assert!(open(u64::MAX, 0, U256::ZERO));
+        assert_eq!(DeviceAccess::reported_expiry(ACCESS_PERMANENT), U256::ZERO);
+    }
+
+    #[test]
+    fn test_consumer_enumeration() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let first = address!("0000000000000000000000000000000000000005");
+        let second = address!("0000000000000000000000000000000000000006");
+        let third = address!("0000000000000000000000000000000000000007");
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.grant_access(first, U256::from(1_000)).is_ok());
+            assert!(contract.grant_access_scope(second, ACCESS_SCOPE_METADATA, U256::ZERO).is_ok());
+            assert!(contract.grant_access(third, U256::ZERO).is_ok());
+            // Re-granting does not list a consumer twice
+            assert!(contract.grant_access_scope(third, ACCESS_SCOPE_RAW, U256::ZERO).is_ok());
+
+            let (consumers, expiries, scopes) = contract.get_consumers(OWNER, U256::ZERO, U256::from(10)).unwrap();
+            assert_eq!(consumers, vec![first, second, third]);
+            assert_eq!(expiries, vec![U256::from(1_000), U256::ZERO, U256::ZERO]);
+            assert_eq!(scopes, vec![ACCESS_SCOPE_ALL, ACCESS_SCOPE_METADATA, ACCESS_SCOPE_ALL]);
+
+            // Pages are clamped to the consumer set
+            let (consumers, _, _) = contract.get_consumers(OWNER, U256::from(1), U256::from(1)).unwrap();
+            assert_eq!(consumers, vec![second]);
+            let (consumers, _, _) = contract.get_consumers(OWNER, U256::from(5), U256::MAX).unwrap();
+            assert!(consumers.is_empty());
+
+            // Lapsed grants are skipped until revoked
+            vm.set_block_timestamp(2_000);
+            let (consumers, _, _) = contract.get_consumers(OWNER, U256::ZERO, U256::from(10)).unwrap();
+            assert_eq!(consumers, vec![second, third]);
+            assert_eq!(contract.get_consumer_count(OWNER).unwrap(), U256::from(3));
+            vm.set_block_timestamp(0);
+
+            // Revoking prunes the consumer and swaps the last entry into its slot
+            assert!(contract.revoke_access(first).is_ok());
+            let (consumers, _, _) = contract.get_consumers(OWNER, U256::ZERO, U256::from(10)).unwrap();
+            assert_eq!(consumers, vec![third, second]);
+
+            // Consumers stay listed while any scope remains
+            assert!(contract.revoke_access(third).is_ok());
+            assert!(contract.revoke_access_scope(second, ACCESS_SCOPE_METADATA).is_ok());
+            let (consumers, _, scopes) = contract.get_consumers(OWNER, U256::ZERO, U256::from(10)).unwrap();
+            assert_eq!(consumers, vec![third]);
+            assert_eq!(scopes, vec![ACCESS_SCOPE_RAW]);
+            assert_eq!(contract.get_consumer_count(OWNER).unwrap(), U256::from(1));
+        });
+    }
+
+    #[test]
+    fn test_device_access() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let buyer = address!("0000000000000000000000000000000000000005");
+        let thermostat = crypto::keccak(b"did:lcore:thermostat");
+        let camera = crypto::keccak(b"did:lcore:camera");
+        mock_device_owner(&vm, thermostat, OWNER, 0);
+        mock_device_owner(&vm, camera, OWNER, 0);
+        mock_device_owner(&vm, B256::ZERO, Address::ZERO, 0);
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.grant_device_access(thermostat, Address::ZERO, U256::ZERO).is_err());
+            assert!(contract.grant_device_access(B256::ZERO, CONSUMER, U256::ZERO).is_err());
+
+            // A device grant covers only that device
+            assert!(contract.grant_device_access(thermostat, CONSUMER, U256::ZERO).is_ok());
+            assert!(contract.has_device_access(thermostat, CONSUMER).unwrap());
+            assert!(!contract.has_device_access(camera, CONSUMER).unwrap());
+            assert!(!contract.has_access(OWNER, CONSUMER).unwrap());
+            assert_eq!(contract.get_device_grant(thermostat, CONSUMER).unwrap(), (OWNER, ACCESS_SCOPE_ALL, U256::ZERO));
+
+            // Owner-wide grants cover every device
+            assert!(contract.grant_access(CONSUMER, U256::ZERO).is_ok());
+            assert!(contract.has_device_access(camera, CONSUMER).unwrap());
+            assert!(contract.revoke_access(CONSUMER).is_ok());
+
+            // Device grants expire on their own schedule
+            vm.set_block_timestamp(1_000);
+            assert!(contract.grant_device_access(camera, CONSUMER, U256::from(1_500)).is_ok());
+            vm.set_block_timestamp(1_500);
+            assert!(contract.has_device_access(camera, CONSUMER).unwrap());
+            vm.set_block_timestamp(1_501);
+            assert!(!contract.has_device_access(camera, CONSUMER).unwrap());
+
+            assert!(contract.revoke_device_access(thermostat, CONSUMER).is_ok());
+            assert!(!contract.has_device_access(thermostat, CONSUMER).unwrap());
+            assert!(contract.grant_device_access(thermostat, CONSUMER, U256::ZERO).is_ok());
+        });
+
+        // Only the device owner grants or revokes
+        with_sender(&vm, CONSUMER, || {
+            assert!(contract.grant_device_access(camera, CONSUMER, U256::ZERO).is_err());
+            assert!(contract.revoke_device_access(thermostat, CONSUMER).is_err());
+        });
+
+        // Grants lapse when the device is transferred...
+        mock_device_owner(&vm, thermostat, buyer, 1);
+        assert!(!contract.has_device_access(thermostat, CONSUMER).unwrap());
+
+        // ... and stay lapsed when it comes back
+        mock_device_owner(&vm, thermostat, OWNER, 2);
+        assert!(!contract.has_device_access(thermostat, CONSUMER).unwrap());
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.grant_device_access(thermostat, CONSUMER, U256::ZERO).is_ok());
+            assert!(contract.has_device_access(thermostat, CONSUMER).unwrap());
+        });
+
+        // Unregistered devices grant nothing
+        assert!(!contract.has_device_access(B256::ZERO, CONSUMER).unwrap());
+    }
+
+    #[test]
+    fn test_access_scopes() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let scopes = ACCESS_SCOPE_AGGREGATED | ACCESS_SCOPE_METADATA;
+
+        with_sender(&vm, OWNER, || {
+            // Zero and unknown scope bits are rejected on every path
+            assert!(contract.grant_access_scope(CONSUMER, 0, U256::ZERO).is_err());
+            assert!(contract.grant_access_scope(CONSUMER, 0x80, U256::ZERO).is_err());
+            assert!(contract.revoke_access_scope(CONSUMER, 0).is_err());
+            assert!(contract.revoke_access_scope(CONSUMER, ACCESS_SCOPE_RAW | 0x40).is_err());
+            assert!(contract.has_access_scope(OWNER, CONSUMER, 0).is_err());
+
+            vm.set_block_timestamp(1_000);
+            assert!(contract.grant_access_scope(CONSUMER, scopes, U256::ZERO).is_ok());
+            assert!(contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_AGGREGATED).unwrap());
+            assert!(contract.has_access_scope(OWNER, CONSUMER, scopes).unwrap());
+            assert!(!contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_RAW).unwrap());
+            assert!(!contract.has_access(OWNER, CONSUMER).unwrap());
+
+            // Each scope keeps its own expiry
+            assert!(contract.grant_access_scope(CONSUMER, ACCESS_SCOPE_RAW, U256::from(2_000)).is_ok());
+            vm.set_block_timestamp(2_001);
+            assert!(!contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_RAW).unwrap());
+            assert!(contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_METADATA).unwrap());
+
+            assert!(contract.revoke_access_scope(CONSUMER, ACCESS_SCOPE_METADATA | ACCESS_SCOPE_RAW).is_ok());
+            assert_eq!(contract.get_access_scopes(OWNER, CONSUMER).unwrap(), ACCESS_SCOPE_AGGREGATED);
+            assert!(!contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_METADATA).unwrap());
+
+            // Full grants cover every scope
+            assert!(contract.grant_access(CONSUMER, U256::ZERO).is_ok());
+            assert!(contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_ALL).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_device_access_scopes() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let meter = crypto::keccak(b"did:lcore:meter");
+        mock_device_owner(&vm, meter, OWNER, 0);
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.grant_device_access_scope(meter, CONSUMER, 0, U256::ZERO).is_err());
+            assert!(contract.grant_device_access_scope(meter, CONSUMER, 0x80, U256::ZERO).is_err());
+            assert!(contract.has_device_access_scope(meter, CONSUMER, 0).is_err());
+
+            assert!(contract.grant_device_access_scope(meter, CONSUMER, ACCESS_SCOPE_AGGREGATED, U256::ZERO).is_ok());
+            assert!(contract.has_device_access_scope(meter, CONSUMER, ACCESS_SCOPE_AGGREGATED).unwrap());
+            assert!(!contract.has_device_access_scope(meter, CONSUMER, ACCESS_SCOPE_RAW).unwrap());
+            assert!(!contract.has_device_access(meter, CONSUMER).unwrap());
+
+            // Owner-wide scopes add to device-level ones
+            assert!(contract.grant_access_scope(CONSUMER, ACCESS_SCOPE_RAW, U256::ZERO).is_ok());
+            assert!(contract.has_device_access_scope(meter, CONSUMER, ACCESS_SCOPE_RAW).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_grant_delegates() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let broker = address!("0000000000000000000000000000000000000007");
+
+        // Only the owner or a trusted delegate grants on the owner's behalf
+        with_sender(&vm, broker, || {
+            assert!(contract.grant_access_for(OWNER, CONSUMER, U256::ZERO).is_err());
+        });
+        with_sender(&vm, OWNER, || {
+            assert!(contract.grant_access_for(OWNER, CONSUMER, U256::ZERO).is_ok());
+            assert!(contract.revoke_access_for(OWNER, CONSUMER).is_ok());
+        });
+
+        with_sender(&vm, ADMIN, || {
+            assert!(contract.set_grant_delegate(Address::ZERO, true).is_err());
+            assert!(contract.set_grant_delegate(broker, true).is_ok());
+        });
+        assert!(contract.is_grant_delegate(broker).unwrap());
+
+        with_sender(&vm, broker, || {
+            assert!(contract.grant_access_for(OWNER, Address::ZERO, U256::ZERO).is_err());
+            assert!(contract.grant_access_for(OWNER, CONSUMER, U256::ZERO).is_ok());
+            assert!(contract.has_access(OWNER, CONSUMER).unwrap());
+            assert!(contract.revoke_access_for(OWNER, CONSUMER).is_ok());
+            assert!(!contract.has_access(OWNER, CONSUMER).unwrap());
+        });
+
+        // Delegated scoped grants reject zero and unknown scope bits like direct ones
+        let meter = crypto::keccak(b"did:lcore:meter");
+        mock_device_owner(&vm, meter, OWNER, 0);
+        with_sender(&vm, broker, || {
+            assert!(contract.grant_access_scope_for(OWNER, CONSUMER, 0, U256::ZERO).is_err());
+            assert!(contract.grant_access_scope_for(OWNER, CONSUMER, 0x80, U256::ZERO).is_err());
+            assert!(contract.revoke_access_scope_for(OWNER, CONSUMER, 0).is_err());
+            assert!(contract.revoke_access_scope_for(OWNER, CONSUMER, ACCESS_SCOPE_RAW | 0x40).is_err());
+            assert!(contract.grant_device_access_scope_for(meter, OWNER, CONSUMER, 0, U256::ZERO).is_err());
+
+            assert!(contract.grant_access_scope_for(OWNER, CONSUMER, ACCESS_SCOPE_RAW, U256::ZERO).is_ok());
+            assert!(contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_RAW).unwrap());
+            assert!(contract.revoke_access_scope_for(OWNER, CONSUMER, ACCESS_SCOPE_RAW).is_ok());
+            assert!(!contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_RAW).unwrap());
+
+            // Device grants on an owner's behalf need that owner to still hold the device
+            assert!(contract
+                .grant_device_access_scope_for(meter, CONSUMER, CONSUMER, ACCESS_SCOPE_METADATA, U256::ZERO)
+                .is_err());
+            assert!(contract
+                .grant_device_access_scope_for(meter, OWNER, CONSUMER, ACCESS_SCOPE_METADATA, U256::ZERO)
+                .is_ok());
+            assert!(contract.has_device_access_scope(meter, CONSUMER, ACCESS_SCOPE_METADATA).unwrap());
+        });
+        mock_device_owner(&vm, meter, CONSUMER, 1);
+        with_sender(&vm, broker, || {
+            let result = contract.grant_device_access_scope_for(meter, OWNER, CONSUMER, ACCESS_SCOPE_METADATA, U256::ZERO);
+            assert_eq!(result.unwrap_err(), b"Device not owned by owner".to_vec());
+        });
+
+        with_sender(&vm, ADMIN, || assert!(contract.set_grant_delegate(broker, false).is_ok()));
+        with_sender(&vm, broker, || {
+            assert!(contract.grant_access_for(OWNER, CONSUMER, U256::ZERO).is_err());
+        });
+    }
+
+    #[test]
+    fn test_operator_grants() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let operator = address!("0000000000000000000000000000000000000008");
+        let device_operators = address!("0000000000000000000000000000000000000009");
+        let meter = crypto::keccak(b"did:lcore:meter");
+        mock_device_owner(&vm, meter, OWNER, 0);
+        let is_manager = IDeviceRegistry::is_device_managerCall { device_id_hash: meter, account: operator }.abi_encode();
+
+        // A per-device operator grants and revokes access to that device only
+        vm.mock_static_call(REGISTRY, is_manager.clone(), Ok(false.abi_encode()));
+        with_sender(&vm, operator, || {
+            let result = contract.grant_device_access(meter, CONSUMER, U256::ZERO);
+            assert_eq!(result.unwrap_err(), b"Only device owner or operator can call this function".to_vec());
+        });
+        vm.mock_static_call(REGISTRY, is_manager, Ok(true.abi_encode()));
+        with_sender(&vm, operator, || {
+            assert!(contract.grant_device_access(meter, CONSUMER, U256::ZERO).is_ok());
+            assert_eq!(contract.get_device_grant(meter, CONSUMER).unwrap().0, OWNER);
+            assert!(contract.has_device_access(meter, CONSUMER).unwrap());
+            assert!(contract.revoke_device_access(meter, CONSUMER).is_ok());
+            assert!(!contract.has_device_access(meter, CONSUMER).unwrap());
+
+            // Owner-wide grants need an operator approved for all of the owner's devices
+            assert!(contract.grant_access_for(OWNER, CONSUMER, U256::ZERO).is_err());
+        });
+
+        vm.mock_static_call(REGISTRY, IDeviceRegistry::device_operatorsCall {}.abi_encode(), Ok(device_operators.abi_encode()));
+        let for_all = IDeviceOperators::is_approved_for_allCall { owner: OWNER, operator }.abi_encode();
+        vm.mock_static_call(device_operators, for_all.clone(), Ok(false.abi_encode()));
+        with_sender(&vm, operator, || {
+            assert_eq!(contract.grant_access_for(OWNER, CONSUMER, U256::ZERO).unwrap_err(), b"Not authorized for owner".to_vec());
+        });
+        vm.mock_static_call(device_operators, for_all, Ok(true.abi_encode()));
+        with_sender(&vm, operator, || {
+            assert!(contract.grant_access_scope_for(OWNER, CONSUMER, ACCESS_SCOPE_RAW, U256::ZERO).is_ok());
+            assert!(contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_RAW).unwrap());
+            assert!(contract.revoke_access_scope_for(OWNER, CONSUMER, ACCESS_SCOPE_RAW).is_ok());
+            assert!(!contract.has_access_scope(OWNER, CONSUMER, ACCESS_SCOPE_RAW).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_admin_functions() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let new_registry = address!("0000000000000000000000000000000000000006");
+
+        with_sender(&vm, ADMIN, || {
+            assert!(contract.initialize(REGISTRY).is_err());
+            assert_eq!(contract.owner().unwrap(), ADMIN);
+            assert_eq!(contract.device_registry().unwrap(), REGISTRY);
+            assert!(contract.set_device_registry(new_registry).is_ok());
+            assert_eq!(contract.device_registry().unwrap(), new_registry);
+        });
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.set_device_registry(REGISTRY).is_err());
+            assert!(contract.set_grant_delegate(OWNER, true).is_err());
+        });
+    }
+}
@@ -0,0 +1,761 @@
+// SPDX-License-Identifier: UNLICENSED
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+// Keep `core::fmt` panic formatting out of the deployed code
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall, SolEvent, SolValue};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{address, hex, Address, B256, U256},
+    crypto, prelude::*,
+};
+
+// Key rotation for devices in the DeviceRegistry, which keeps the key a device was
+// registered with; this contract records every key the device has used since
+sol_storage! {
+    #[entrypoint]
+    pub struct DeviceKeys {
+        /// Admin and configuration
+        address admin;
+        address device_registry_address;
+        address p256_verifier_address;           // Pure-Rust fallback when RIP-7212 is unavailable
+
+        /// Key history (device_id_hash -> keys, oldest first); empty until the first
+        /// rotation, which records the registration key as entry 0
+        mapping(bytes32 => KeyRecord[]) device_key_history;
+    }
+
+    pub struct KeyRecord {
+        string public_key;
+        uint256 valid_from;
+        uint256 valid_until; // 0 while the key is current
+    }
+}
+
+/// Domain tags for registration and key rotation digests
+const REGISTRATION_TAG: &[u8] = b"LCORE_DEVICE_REGISTRATION";
+const KEY_ROTATION_TAG: &[u8] = b"LCORE_KEY_ROTATION";
+
+/// DeviceRegistry lifecycle states that restrict key rotation
+const DEVICE_STATUS_SUSPENDED: u8 = 2;
+const DEVICE_STATUS_DECOMMISSIONED: u8 = 3;
+
+/// secp256k1 n / 2; ecrecover signatures with a higher s are malleable
+const SECP256K1_HALF_N: U256 = U256::from_be_slice(&[
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+]);
+
+const ECRECOVER_PRECOMPILE: Address = address!("0000000000000000000000000000000000000001");
+const P256_VERIFY_PRECOMPILE: Address = address!("0000000000000000000000000000000000000100"); // RIP-7212
+
+/// Key history entry returned by `get_device_key`: (public_key, valid_from, valid_until)
+type KeyEntry = (String, U256, U256);
+
+sol! {
+    event DeviceKeyRotated(
+        bytes32 indexed device_id_hash,
+        uint256 key_index,
+        uint256 valid_from
+    );
+}
+
+// Interface for DeviceRegistry contract calls
+sol! {
+    interface IDeviceRegistry {
+        function is_device_registered(bytes32 device_id_hash) external view returns (bool);
+        function get_device_owner(bytes32 device_id_hash) external view returns (address);
+        function is_device_manager(bytes32 device_id_hash, address account) external view returns (bool);
+        function get_device_status(bytes32 device_id_hash) external view returns (uint8);
+        function get_device_public_key(bytes32 device_id_hash) external view returns (string);
+        function get_device_registered_at(bytes32 device_id_hash) external view returns (uint256);
+    }
+}
+
+#[public]
+impl DeviceKeys {
+    /// Initialize the contract with the DeviceRegistry whose devices it tracks
+    pub fn initialize(&mut self, device_registry: Address) -> Result<(), Vec<u8>> {
+        if self.admin.get() != Address::ZERO {
+            return Err(b"Already initialized".to_vec());
+        }
+        self.admin.set(self.vm().msg_sender());
+        self.device_registry_address.set(device_registry);
+        Ok(())
+    }
+
+    /// Rotate a device public key. `proof` signs `key_rotation_digest` and is either a
+    /// 65-byte (r, s, v) personal_sign signature by the device owner (or an operator it
+    /// approved in DeviceOperators) or a 64-byte (r, s)
+    /// ES-256 signature by the current device key; `new_key_proof` is a 64-byte ES-256
+    /// signature of the same digest by the new key
+    pub fn rotate_device_key(
+        &mut self,
+        device_id_hash: B256,
+        new_key: String,
+        proof: Bytes,
+        new_key_proof: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        let owner = self.registry_device_owner(device_id_hash)?;
+        if owner == Address::ZERO {
+            return Err(b"Device not registered".to_vec());
+        }
+        let status = self.registry_device_status(device_id_hash)?;
+        if status == DEVICE_STATUS_DECOMMISSIONED {
+            return Err(b"Device decommissioned".to_vec());
+        }
+        if Self::parse_p256_key(&new_key).is_none() {
+            return Err(b"Invalid public key".to_vec());
+        }
+
+        // The new key signing the digest also proves it is a point on the curve
+        let digest = self.key_rotation_digest(device_id_hash, new_key.clone())?;
+        if !self.verify_es256(&new_key, digest, &new_key_proof) {
+            return Err(b"Invalid new key proof".to_vec());
+        }
+        let authorized = match proof.len() {
+            65 => {
+                let signed = Self::eth_signed_message_hash(digest);
+                let r = B256::from_slice(&proof[0..32]);
+                let s = B256::from_slice(&proof[32..64]);
+                self.recover_signer(signed, proof[64], r, s)
+                    .is_some_and(|signer| signer == owner || self.registry_is_device_manager(device_id_hash, signer))
+            }
+            64 => {
+                // A suspended device may have a compromised key, so only its owner can rotate it
+                if status == DEVICE_STATUS_SUSPENDED {
+                    return Err(b"Device suspended".to_vec());
+                }
+                let current_key = self.current_device_key(device_id_hash)?;
+                self.verify_es256(&current_key, digest, &proof)
+            }
+            _ => return Err(b"Invalid proof length".to_vec()),
+        };
+        if !authorized {
+            return Err(b"Invalid key rotation proof".to_vec());
+        }
+
+        // The first rotation records the registration key, which was valid until now
+        if self.device_key_history.getter(device_id_hash).is_empty() {
+            let registration_key = self.registry_device_public_key(device_id_hash)?;
+            let registered_at = self.registry_device_registered_at(device_id_hash)?;
+            self.push_key_record(device_id_hash, registration_key, registered_at);
+        }
+
+        let now = U256::from(self.vm().block_timestamp());
+        let mut history = self.device_key_history.setter(device_id_hash);
+        let key_index = history.len();
+        if let Some(mut current) = history.setter(key_index - 1) {
+            current.valid_until.set(now);
+        }
+        self.push_key_record(device_id_hash, new_key, now);
+
+        self.emit(DeviceKeyRotated {
+            device_id_hash,
+            key_index: U256::from(key_index),
+            valid_from: now,
+        });
+
+        Ok(())
+    }
+
+    /// Digest to sign for the next key rotation of a device
+    pub fn key_rotation_digest(&self, device_id_hash: B256, new_key: String) -> Result<B256, Vec<u8>> {
+        let nonce = U256::from(self.device_key_history.getter(device_id_hash).len());
+        let encoded = (
+            crypto::keccak(KEY_ROTATION_TAG),
+            U256::from(self.vm().chain_id()),
+            self.vm().contract_address(),
+            device_id_hash,
+            crypto::keccak(new_key.as_bytes()),
+            nonce,
+        )
+            .abi_encode();
+        Ok(crypto::keccak(encoded))
+    }
+
+    /// Digest a device key signs to prove possession when `device_id` is registered to
+    /// `owner`; binds the chain and the DeviceRegistry so proofs cannot be replayed elsewhere
+    pub fn registration_digest(&self, device_id: String, owner: Address) -> Result<B256, Vec<u8>> {
+        let encoded = (
+            crypto::keccak(REGISTRATION_TAG),
+            U256::from(self.vm().chain_id()),
+            self.device_registry_address.get(),
+            crypto::keccak(device_id.as_bytes()),
+            owner,
+        )
+            .abi_encode();
+        Ok(crypto::keccak(encoded))
+    }
+
+    /// Check a registration proof of possession: an ES-256 signature of
+    /// `registration_digest` by the key being registered
+    pub fn verify_registration(
+        &self,
+        device_id: String,
+        owner: Address,
+        public_key_hex: String,
+        r: B256,
+        s: B256,
+    ) -> Result<bool, Vec<u8>> {
+        let digest = self.registration_digest(device_id, owner)?;
+        Ok(self.verify_es256(&public_key_hex, digest, &[r.as_slice(), s.as_slice()].concat()))
+    }
+
+    /// Get the current public key of a device (its registration key until the first rotation)
+    pub fn current_device_key(&self, device_id_hash: B256) -> Result<String, Vec<u8>> {
+        let history = self.device_key_history.getter(device_id_hash);
+        match history.len().checked_sub(1).and_then(|last| history.getter(last)) {
+            Some(record) => Ok(record.public_key.get_string()),
+            None => self.registry_device_public_key(device_id_hash),
+        }
+    }
+
+    /// Get the public key that was valid for a device at `timestamp` (empty if none)
+    pub fn key_at(&self, device_id_hash: B256, timestamp: U256) -> Result<String, Vec<u8>> {
+        for i in (0..self.get_device_key_count(device_id_hash)?.to::<usize>()).rev() {
+            let (public_key, valid_from, valid_until) = self.get_device_key(device_id_hash, U256::from(i))?;
+            if valid_from <= timestamp && (valid_until.is_zero() || timestamp < valid_until) {
+                return Ok(public_key);
+            }
+        }
+        Ok(String::new())
+    }
+
+    /// Get the number of keys a device has used, including its registration key
+    pub fn get_device_key_count(&self, device_id_hash: B256) -> Result<U256, Vec<u8>> {
+        let rotated = self.device_key_history.getter(device_id_hash).len();
+        if rotated == 0 && self.registry_device_owner(device_id_hash)? != Address::ZERO {
+            return Ok(U256::from(1));
+        }
+        Ok(U256::from(rotated))
+    }
+
+    /// Get a key history entry: (public_key, valid_from, valid_until); valid_until is 0 for the current key
+    pub fn get_device_key(&self, device_id_hash: B256, index: U256) -> Result<KeyEntry, Vec<u8>> {
+        let history = self.device_key_history.getter(device_id_hash);
+        if let Some(record) = history.getter(index) {
+            return Ok((
+                record.public_key.get_string(),
+                record.valid_from.get(),
+                record.valid_until.get(),
+            ));
+        }
+        if history.is_empty() && index.is_zero() && self.registry_device_owner(device_id_hash)? != Address::ZERO {
+            return Ok((
+                self.registry_device_public_key(device_id_hash)?,
+                self.registry_device_registered_at(device_id_hash)?,
+                U256::ZERO,
+            ));
+        }
+        Err(b"Key index out of bounds".to_vec())
+    }
+
+    /// Verify an ES-256 signature over `message_hash` made with the device's current key
+    /// Always false for unregistered, suspended or decommissioned devices
+    pub fn verify_device_signature(
+        &self,
+        device_id_hash: B256,
+        message_hash: B256,
+        r: B256,
+        s: B256,
+    ) -> Result<bool, Vec<u8>> {
+        if !self.registry_device_in_service(device_id_hash)? {
+            return Ok(false);
+        }
+        let public_key = self.current_device_key(device_id_hash)?;
+        Ok(self.verify_es256(&public_key, message_hash, &[r.as_slice(), s.as_slice()].concat()))
+    }
+
+    // ========== Admin Functions ==========
+
+    /// Get contract owner
+    pub fn owner(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.admin.get())
+    }
+
+    /// Get the DeviceRegistry whose devices are tracked
+    pub fn device_registry(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.device_registry_address.get())
+    }
+
+    /// Update DeviceRegistry address
+    pub fn set_device_registry(&mut self, device_registry: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.device_registry_address.set(device_registry);
+        Ok(())
+    }
+
+    /// Get the fallback P-256 verifier used when the RIP-7212 precompile is unavailable
+    pub fn p256_verifier(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.p256_verifier_address.get())
+    }
+
+    /// Update the fallback P-256 verifier (zero disables the fallback)
+    pub fn set_p256_verifier(&mut self, verifier: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.p256_verifier_address.set(verifier);
+        Ok(())
+    }
+}
+
+// Private helper functions
+impl DeviceKeys {
+    /// Emit an event; unlike `stylus_sdk::prelude::log` this cannot panic, which keeps
+    /// panic formatting out of the contract
+    fn emit<T: SolEvent>(&self, event: T) {
+        let mut topics = [alloy_sol_types::abi::token::WordToken::default(); 4];
+        let _ = event.encode_topics_raw(&mut topics);
+        let count = <T::TopicList as alloy_sol_types::TopicList>::COUNT;
+        let mut data = Vec::with_capacity(32 * count);
+        for topic in &topics[..count] {
+            data.extend_from_slice(topic.as_slice());
+        }
+        event.encode_data_to(&mut data);
+        self.vm().emit_log(&data, count);
+    }
+
+    /// Ensure only contract owner can call
+    fn only_owner(&self) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != self.admin.get() {
+            return Err(b"Only owner can call this function".to_vec());
+        }
+        Ok(())
+    }
+
+    /// Append a key to a device's key history, valid from `valid_from`
+    fn push_key_record(&mut self, device_id_hash: B256, public_key: String, valid_from: U256) {
+        let mut history = self.device_key_history.setter(device_id_hash);
+        let mut record = history.grow();
+        record.public_key.set_str(public_key);
+        record.valid_from.set(valid_from);
+        record.valid_until.set(U256::ZERO);
+    }
+
+    /// Static call into the DeviceRegistry
+    fn registry_call(&self, calldata: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+        let registry = self.device_registry_address.get();
+        Ok(self.vm().static_call(&self, registry, calldata)?)
+    }
+
+    /// Check with the DeviceRegistry that a device is registered and in service
+    fn registry_device_in_service(&self, device_id_hash: B256) -> Result<bool, Vec<u8>> {
+        let response = self.registry_call(&IDeviceRegistry::is_device_registeredCall { device_id_hash }.abi_encode())?;
+        let (in_service,) = <(bool,)>::abi_decode(&response, true)
+            .map_err(|_| b"Failed to decode registry response".to_vec())?;
+        Ok(in_service)
+    }
+
+    /// Get device owner from the DeviceRegistry (zero if unregistered)
+    fn registry_device_owner(&self, device_id_hash: B256) -> Result<Address, Vec<u8>> {
+        let response = self.registry_call(&IDeviceRegistry::get_device_ownerCall { device_id_hash }.abi_encode())?;
+        let (owner,) = <(Address,)>::abi_decode(&response, true)
+            .map_err(|_| b"Failed to decode owner response".to_vec())?;
+        Ok(owner)
+    }
+
+    /// Check with the DeviceRegistry whether `account` owns or is an approved operator of a device
+    fn registry_is_device_manager(&self, device_id_hash: B256, account: Address) -> bool {
+        let calldata = IDeviceRegistry::is_device_managerCall { device_id_hash, account }.abi_encode();
+        self.registry_call(&calldata)
+            .is_ok_and(|response| response.len() == 32 && U256::from_be_slice(&response) == U256::from(1))
+    }
+
+    /// Get device lifecycle status from the DeviceRegistry
+    fn registry_device_status(&self, device_id_hash: B256) -> Result<u8, Vec<u8>> {
+        let response = self.registry_call(&IDeviceRegistry::get_device_statusCall { device_id_hash }.abi_encode())?;
+        let status = IDeviceRegistry::get_device_statusCall::abi_decode_returns(&response, true)
+            .map_err(|_| b"Failed to decode status response".to_vec())?;
+        Ok(status._0)
+    }
+
+    /// Get the key a device was registered with from the DeviceRegistry
+    fn registry_device_public_key(&self, device_id_hash: B256) -> Result<String, Vec<u8>> {
+        let response =
+            self.registry_call(&IDeviceRegistry::get_device_public_keyCall { device_id_hash }.abi_encode())?;
+        let public_key = IDeviceRegistry::get_device_public_keyCall::abi_decode_returns(&response, true)
+            .map_err(|_| b"Failed to decode public key response".to_vec())?;
+        Ok(public_key._0)
+    }
+
+    /// Get device registration timestamp from the DeviceRegistry
+    fn registry_device_registered_at(&self, device_id_hash: B256) -> Result<U256, Vec<u8>> {
+        let response =
+            self.registry_call(&IDeviceRegistry::get_device_registered_atCall { device_id_hash }.abi_encode())?;
+        let (registered_at,) = <(U256,)>::abi_decode(&response, true)
+            .map_err(|_| b"Failed to decode timestamp response".to_vec())?;
+        Ok(registered_at)
+    }
+
+    /// Hash a 32-byte digest with the EIP-191 personal_sign prefix
+    fn eth_signed_message_hash(digest: B256) -> B256 {
+        let mut message = b"\x19Ethereum Signed Message:\n32".to_vec();
+        message.extend_from_slice(digest.as_slice());
+        crypto::keccak(message)
+    }
+
+    /// Recover the secp256k1 signer of `hash` via the ecrecover precompile
+    fn recover_signer(&self, hash: B256, v: u8, r: B256, s: B256) -> Option<Address> {
+        // Reject malleable (high-s) signatures
+        if U256::from_be_bytes(s.0) > SECP256K1_HALF_N {
+            return None;
+        }
+        let v = if v < 27 { v + 27 } else { v };
+        let calldata = (hash, U256::from(v), r, s).abi_encode();
+        let response = self.vm().static_call(&self, ECRECOVER_PRECOMPILE, &calldata).ok()?;
+        if response.len() != 32 {
+            return None;
+        }
+        let signer = Address::from_slice(&response[12..32]);
+        (signer != Address::ZERO).then_some(signer)
+    }
+
+    /// Verify a 64-byte (r, s) ES-256 signature against a hex-encoded public key, using
+    /// the RIP-7212 precompile when available and the fallback verifier otherwise
+    fn verify_es256(&self, public_key_hex: &str, message_hash: B256, signature: &[u8]) -> bool {
+        let Some(point) = Self::parse_p256_key(public_key_hex) else {
+            return false;
+        };
+        if signature.len() != 64 {
+            return false;
+        }
+
+        // Precompile input: hash || r || s || x || y; returns uint256(1) on success and
+        // empty output on failure or when the precompile is not deployed, so only empty
+        // output is retried with the fallback verifier, which has the same interface
+        let calldata = [message_hash.as_slice(), signature, &point].concat();
+        let valid = |response: Vec<u8>| response.len() == 32 && U256::from_be_slice(&response) == U256::from(1);
+        match self.vm().static_call(&self, P256_VERIFY_PRECOMPILE, &calldata) {
+            Ok(response) if response.is_empty() => {
+                let verifier = self.p256_verifier_address.get();
+                verifier != Address::ZERO
+                    && self.vm().static_call(&self, verifier, &calldata).is_ok_and(valid)
+            }
+            Ok(response) => valid(response),
+            Err(_) => false,
+        }
+    }
+
+    /// Parse a hex-encoded uncompressed SEC1 P-256 public key (04 || x || y) into x || y
+    fn parse_p256_key(public_key_hex: &str) -> Option<Vec<u8>> {
+        let key_bytes = hex::decode(public_key_hex).ok()?;
+        match key_bytes.split_first() {
+            Some((0x04, point)) if point.len() == 64 => Some(point.to_vec()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p256::ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey};
+    use stylus_sdk::testing::*;
+
+    const ADMIN: Address = address!("0000000000000000000000000000000000000001");
+    const OWNER: Address = address!("0000000000000000000000000000000000000002");
+    const REGISTRY: Address = address!("0000000000000000000000000000000000000004");
+
+    /// Deterministic P-256 test key, with its hex-encoded uncompressed SEC1 public key
+    fn test_device_key(seed: &str) -> (SigningKey, String) {
+        let key = SigningKey::from_slice(crypto::keccak(seed.as_bytes()).as_slice()).unwrap();
+        let public_key = hex::encode(key.verifying_key().to_encoded_point(false).as_bytes());
+        (key, public_key)
+    }
+
+    /// ES-256 signature of `digest` as a 64-byte (r, s) proof, which the mocked
+    /// RIP-7212 precompile accepts for `key`
+    fn es256_proof(vm: &TestVM, key: &SigningKey, digest: B256) -> Bytes {
+        let signature: Signature = key.sign_prehash(digest.as_slice()).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+        let input = [digest.as_slice(), &signature.to_bytes(), &point.as_bytes()[1..]].concat();
+        vm.mock_static_call(P256_VERIFY_PRECOMPILE, input, Ok(U256::from(1).to_be_bytes_vec()));
+        signature.to_bytes().to_vec().into()
+    }
+
+    /// Mock the DeviceRegistry views for a registered device
+    fn mock_registered_device(vm: &TestVM, device_id_hash: B256, status: u8, public_key: &str, registered_at: u64) {
+        let mock = |calldata: Vec<u8>, response: Vec<u8>| vm.mock_static_call(REGISTRY, calldata, Ok(response));
+        mock(IDeviceRegistry::get_device_ownerCall { device_id_hash }.abi_encode(), OWNER.abi_encode());
+        mock(IDeviceRegistry::get_device_statusCall { device_id_hash }.abi_encode(), U256::from(status).abi_encode());
+        mock(
+            IDeviceRegistry::get_device_public_keyCall { device_id_hash }.abi_encode(),
+            public_key.abi_encode(),
+        );
+        mock(
+            IDeviceRegistry::get_device_registered_atCall { device_id_hash }.abi_encode(),
+            U256::from(registered_at).abi_encode(),
+        );
+    }
+
+    fn setup(vm: &TestVM) -> DeviceKeys {
+        let mut contract = DeviceKeys::from(vm);
+        vm.set_sender(ADMIN);
+        assert!(contract.initialize(REGISTRY).is_ok());
+        vm.set_sender(OWNER);
+        contract
+    }
+
+    #[test]
+    fn test_device_key_rotation() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let device_hash = crypto::keccak(b"did:lcore:rotating");
+        let (current_key, current_hex) = test_device_key("did:lcore:rotating");
+        let (next_key, next_hex) = test_device_key("did:lcore:rotating:next");
+
+        vm.set_block_timestamp(100);
+        mock_registered_device(&vm, device_hash, 1, &current_hex, 10);
+        assert_eq!(contract.get_device_key_count(device_hash).unwrap(), U256::from(1));
+        assert_eq!(contract.current_device_key(device_hash).unwrap(), current_hex);
+
+        // Keys that are not hex-encoded SEC1 P-256 points are rejected before the proof is checked
+        let compressed = hex::encode(current_key.verifying_key().to_encoded_point(true).as_bytes());
+        for bad_key in ["", "zz", "04ab", &current_hex[2..], &compressed] {
+            let digest = contract.key_rotation_digest(device_hash, bad_key.into()).unwrap();
+            let proof = es256_proof(&vm, &current_key, digest);
+            let result = contract.rotate_device_key(device_hash, bad_key.into(), proof.clone(), proof);
+            assert_eq!(result.unwrap_err(), b"Invalid public key".to_vec());
+        }
+
+        // Current device key signs the rotation digest, and the new key proves possession
+        let digest = contract.key_rotation_digest(device_hash, next_hex.clone()).unwrap();
+        let proof = es256_proof(&vm, &current_key, digest);
+        let new_key_proof = es256_proof(&vm, &next_key, digest);
+        let result = contract.rotate_device_key(device_hash, next_hex.clone(), proof.clone(), new_key_proof.clone());
+        assert!(result.is_ok(), "Key rotation should succeed");
+
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.last().unwrap().0[0], DeviceKeyRotated::SIGNATURE_HASH);
+
+        // Replaying the same proofs fails because the rotation nonce advanced
+        assert!(contract.rotate_device_key(device_hash, next_hex.clone(), proof, new_key_proof).is_err());
+
+        assert_eq!(contract.current_device_key(device_hash).unwrap(), next_hex);
+        assert_eq!(contract.get_device_key_count(device_hash).unwrap(), U256::from(2));
+        let (previous_key, valid_from, valid_until) = contract.get_device_key(device_hash, U256::ZERO).unwrap();
+        assert_eq!(previous_key, current_hex);
+        assert_eq!((valid_from, valid_until), (U256::from(10), U256::from(100)));
+        assert!(contract.get_device_key(device_hash, U256::from(2)).is_err());
+
+        // Historical signatures resolve to the key that was valid when they were made
+        assert_eq!(contract.key_at(device_hash, U256::from(9)).unwrap(), "");
+        assert_eq!(contract.key_at(device_hash, U256::from(10)).unwrap(), current_hex);
+        assert_eq!(contract.key_at(device_hash, U256::from(99)).unwrap(), current_hex);
+        assert_eq!(contract.key_at(device_hash, U256::from(100)).unwrap(), next_hex);
+        assert_eq!(contract.key_at(device_hash, U256::from(5000)).unwrap(), next_hex);
+    }
+
+    #[test]
+    fn test_key_rotation_requires_new_key_proof() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let device_hash = crypto::keccak(b"did:lcore:unproven");
+        let (current_key, current_hex) = test_device_key("did:lcore:unproven");
+        let (next_key, next_hex) = test_device_key("did:lcore:unproven:next");
+        mock_registered_device(&vm, device_hash, 1, &current_hex, 10);
+
+        let digest = contract.key_rotation_digest(device_hash, next_hex.clone()).unwrap();
+        let proof = es256_proof(&vm, &current_key, digest);
+
+        // Missing new key proof
+        let result = contract.rotate_device_key(device_hash, next_hex.clone(), proof.clone(), Bytes::from(Vec::new()));
+        assert_eq!(result.unwrap_err(), b"Invalid new key proof".to_vec());
+
+        // Signed by the current key instead of the new key
+        let result = contract.rotate_device_key(device_hash, next_hex.clone(), proof.clone(), proof.clone());
+        assert_eq!(result.unwrap_err(), b"Invalid new key proof".to_vec());
+
+        // New key signing a different digest
+        let wrong_digest = crypto::keccak(b"not the rotation digest");
+        let result =
+            contract.rotate_device_key(device_hash, next_hex.clone(), proof.clone(), es256_proof(&vm, &next_key, wrong_digest));
+        assert_eq!(result.unwrap_err(), b"Invalid new key proof".to_vec());
+
+        // Valid new key proof, but the rotation proof is not from the current key
+        let result =
+            contract.rotate_device_key(device_hash, next_hex.clone(), es256_proof(&vm, &next_key, digest), es256_proof(&vm, &next_key, digest));
+        assert_eq!(result.unwrap_err(), b"Invalid key rotation proof".to_vec());
+        assert!(contract.rotate_device_key(device_hash, next_hex, Bytes::from(Vec::new()), es256_proof(&vm, &next_key, digest)).is_err());
+
+        assert_eq!(contract.current_device_key(device_hash).unwrap(), current_hex);
+    }
+
+    #[test]
+    fn test_owner_key_rotation() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let device_hash = crypto::keccak(b"did:lcore:owned");
+        let (current_key, current_hex) = test_device_key("did:lcore:owned");
+        let (next_key, next_hex) = test_device_key("did:lcore:owned:next");
+        mock_registered_device(&vm, device_hash, DEVICE_STATUS_SUSPENDED, &current_hex, 10);
+
+        let digest = contract.key_rotation_digest(device_hash, next_hex.clone()).unwrap();
+        let new_key_proof = es256_proof(&vm, &next_key, digest);
+
+        // A suspended device's current key can no longer rotate itself
+        let proof = es256_proof(&vm, &current_key, digest);
+        let result = contract.rotate_device_key(device_hash, next_hex.clone(), proof, new_key_proof.clone());
+        assert_eq!(result.unwrap_err(), b"Device suspended".to_vec());
+
+        // The owner's personal_sign signature is checked with ecrecover
+        let r = B256::repeat_byte(0x11);
+        let s = B256::repeat_byte(0x22);
+        let ecrecover_input = (DeviceKeys::eth_signed_message_hash(digest), U256::from(28), r, s).abi_encode();
+        let owner_proof: Bytes = [r.as_slice(), s.as_slice(), &[1]].concat().into();
+
+        let stranger = address!("0000000000000000000000000000000000000009");
+        vm.mock_static_call(ECRECOVER_PRECOMPILE, ecrecover_input.clone(), Ok(B256::left_padding_from(stranger.as_slice()).to_vec()));
+        let result = contract.rotate_device_key(device_hash, next_hex.clone(), owner_proof.clone(), new_key_proof.clone());
+        assert_eq!(result.unwrap_err(), b"Invalid key rotation proof".to_vec());
+
+        vm.mock_static_call(ECRECOVER_PRECOMPILE, ecrecover_input, Ok(B256::left_padding_from(OWNER.as_slice()).to_vec()));
+        assert!(contract.rotate_device_key(device_hash, next_hex.clone(), owner_proof.clone(), new_key_proof).is_ok());
+        assert_eq!(contract.current_device_key(device_hash).unwrap(), next_hex);
+
+        // An operator the owner approved signs rotations on its behalf
+        let digest = contract.key_rotation_digest(device_hash, current_hex.clone()).unwrap();
+        let new_key_proof = es256_proof(&vm, &current_key, digest);
+        let ecrecover_input = (DeviceKeys::eth_signed_message_hash(digest), U256::from(28), r, s).abi_encode();
+        vm.mock_static_call(ECRECOVER_PRECOMPILE, ecrecover_input, Ok(B256::left_padding_from(stranger.as_slice()).to_vec()));
+        let is_manager = IDeviceRegistry::is_device_managerCall { device_id_hash: device_hash, account: stranger }.abi_encode();
+        vm.mock_static_call(REGISTRY, is_manager.clone(), Ok(false.abi_encode()));
+        let result = contract.rotate_device_key(device_hash, current_hex.clone(), owner_proof.clone(), new_key_proof.clone());
+        assert_eq!(result.unwrap_err(), b"Invalid key rotation proof".to_vec());
+        vm.mock_static_call(REGISTRY, is_manager, Ok(true.abi_encode()));
+        assert!(contract.rotate_device_key(device_hash, current_hex.clone(), owner_proof, new_key_proof).is_ok());
+        assert_eq!(contract.current_device_key(device_hash).unwrap(), current_hex);
+
+        // Decommissioned devices keep their last key
+        mock_registered_device(&vm, device_hash, DEVICE_STATUS_DECOMMISSIONED, &current_hex, 10);
+        let digest = contract.key_rotation_digest(device_hash, current_hex.clone()).unwrap();
+        let result = contract.rotate_device_key(
+            device_hash,
+            current_hex,
+            es256_proof(&vm, &next_key, digest),
+            es256_proof(&vm, &current_key, digest),
+        );
+        assert_eq!(result.unwrap_err(), b"Device decommissioned".to_vec());
+    }
+
+    #[test]
+    fn test_unregistered_device_has_no_keys() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let device_hash = crypto::keccak(b"did:lcore:unknown");
+        let (key, public_key) = test_device_key("did:lcore:unknown");
+        vm.mock_static_call(
+            REGISTRY,
+            IDeviceRegistry::get_device_ownerCall { device_id_hash: device_hash }.abi_encode(),
+            Ok(Address::ZERO.abi_encode()),
+        );
+
+        assert_eq!(contract.get_device_key_count(device_hash).unwrap(), U256::ZERO);
+        assert!(contract.get_device_key(device_hash, U256::ZERO).is_err());
+        assert_eq!(contract.key_at(device_hash, U256::from(1)).unwrap(), "");
+
+        let digest = contract.key_rotation_digest(device_hash, public_key.clone()).unwrap();
+        let proof = es256_proof(&vm, &key, digest);
+        let result = contract.rotate_device_key(device_hash, public_key, proof.clone(), proof);
+        assert_eq!(result.unwrap_err(), b"Device not registered".to_vec());
+    }
+
+    #[test]
+    fn test_registration_proof_of_possession() {
+        let vm = TestVM::default();
+        let contract = setup(&vm);
+        let (key, public_key) = test_device_key("did:lcore:pop");
+        let (other_key, other_public_key) = test_device_key("did:lcore:other");
+        let squatter = address!("0000000000000000000000000000000000000009");
+
+        let digest = contract.registration_digest("did:lcore:pop".into(), OWNER).unwrap();
+        let proof = es256_proof(&vm, &key, digest);
+        let verify = |device_id: &str, owner: Address, public_key: &str, proof: &Bytes| {
+            let (r, s) = proof.split_at(32.min(proof.len()));
+            let (r, s) = (B256::left_padding_from(r), B256::left_padding_from(s));
+            contract.verify_registration(device_id.into(), owner, public_key.into(), r, s).unwrap()
+        };
+        assert!(verify("did:lcore:pop", OWNER, &public_key, &proof));
+
+        // The proof is bound to the device, the owner and the key that signed it
+        assert!(!verify("did:lcore:other", OWNER, &public_key, &proof));
+        assert!(!verify("did:lcore:pop", squatter, &public_key, &proof));
+        assert!(!verify("did:lcore:pop", OWNER, &other_public_key, &proof));
+        assert!(!verify("did:lcore:pop", OWNER, &other_public_key, &es256_proof(&vm, &other_key, crypto::keccak(b"other"))));
+
+        // Claiming another device's key needs a signature from that key
+        let squatter_digest = contract.registration_digest("did:lcore:pop".into(), squatter).unwrap();
+        assert_ne!(squatter_digest, digest);
+        assert!(!verify("did:lcore:pop", squatter, &public_key, &es256_proof(&vm, &other_key, squatter_digest)));
+        assert!(!verify("did:lcore:pop", OWNER, &public_key, &Bytes::from(Vec::new())));
+    }
+
+    #[test]
+    fn test_verify_device_signature() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let verifier = address!("0000000000000000000000000000000000000006");
+        let device_hash = crypto::keccak(b"did:lcore:signer");
+        let (key, public_key) = test_device_key("did:lcore:signer");
+        mock_registered_device(&vm, device_hash, 1, &public_key, 10);
+        let in_service = IDeviceRegistry::is_device_registeredCall { device_id_hash: device_hash }.abi_encode();
+
+        let message_hash = crypto::keccak(b"reading");
+        let signature: Signature = key.sign_prehash(message_hash.as_slice()).unwrap();
+        let (r, s) = signature.split_bytes();
+        let (r, s) = (B256::from_slice(&r), B256::from_slice(&s));
+        let point = key.verifying_key().to_encoded_point(false);
+        let input = [message_hash.as_slice(), r.as_slice(), s.as_slice(), &point.as_bytes()[1..]].concat();
+        let valid = U256::from(1).to_be_bytes_vec();
+
+        // Suspended and decommissioned devices never verify
+        vm.mock_static_call(REGISTRY, in_service.clone(), Ok(false.abi_encode()));
+        vm.mock_static_call(P256_VERIFY_PRECOMPILE, input.clone(), Ok(valid.clone()));
+        assert!(!contract.verify_device_signature(device_hash, message_hash, r, s).unwrap());
+
+        vm.mock_static_call(REGISTRY, in_service, Ok(true.abi_encode()));
+        assert!(contract.verify_device_signature(device_hash, message_hash, r, s).unwrap());
+        assert!(!contract.verify_device_signature(device_hash, message_hash, s, r).unwrap());
+
+        // Empty precompile output falls back to the verifier contract once one is configured
+        vm.mock_static_call(P256_VERIFY_PRECOMPILE, input.clone(), Ok(Vec::new()));
+        assert!(!contract.verify_device_signature(device_hash, message_hash, r, s).unwrap());
+        vm.set_sender(ADMIN);
+        assert!(contract.set_p256_verifier(verifier).is_ok());
+        assert_eq!(contract.p256_verifier().unwrap(), verifier);
+        assert!(!contract.verify_device_signature(device_hash, message_hash, r, s).unwrap());
+        vm.mock_static_call(verifier, input.clone(), Ok(valid));
+        assert!(contract.verify_device_signature(device_hash, message_hash, r, s).unwrap());
+
+        // A failing precompile call is not retried
+        vm.mock_static_call(P256_VERIFY_PRECOMPILE, input, Err(Vec::new()));
+        assert!(!contract.verify_device_signature(device_hash, message_hash, r, s).unwrap());
+    }
+
+    #[test]
+    fn test_admin_functions() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let new_registry = address!("0000000000000000000000000000000000000005");
+
+        assert!(contract.initialize(new_registry).is_err());
+        assert!(contract.set_device_registry(new_registry).is_err());
+        assert!(contract.set_p256_verifier(new_registry).is_err());
+
+        vm.set_sender(ADMIN);
+        assert!(contract.set_device_registry(new_registry).is_ok());
+        assert_eq!(contract.device_registry().unwrap(), new_registry);
+        assert_eq!(contract.owner().unwrap(), ADMIN);
+    }
+}
@@ -0,0 +1,366 @@
+// SPDX-License-Identifier: UNLICENSED
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+// Keep `core::fmt` panic formatting out of the deployed code
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall, SolEvent, SolValue};
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256},
+    prelude::*,
+};
+
+// ERC-721-style operator approvals for devices in the DeviceRegistry; the registry and
+// its companions ask this contract whether an account may manage a device for its owner
+sol_storage! {
+    #[entrypoint]
+    pub struct DeviceOperators {
+        /// Admin and configuration
+        address admin;
+        address device_registry_address;
+
+        /// Per-device approvals (device_id_hash -> approval)
+        mapping(bytes32 => DeviceApproval) device_approvals;
+
+        /// Operators approved for all of an owner's devices
+        mapping(address => mapping(address => bool)) operator_approvals; // owner -> operator -> approved
+    }
+
+    /// Per-device approval, honoured only while the device has not changed hands since
+    /// it was given (`owner_epoch`)
+    pub struct DeviceApproval {
+        address operator;
+        uint256 owner_epoch;
+    }
+}
+
+sol! {
+    /// ERC-721 compatible; token id is uint256(device_id_hash)
+    event Approval(
+        address indexed owner,
+        address indexed approved,
+        uint256 indexed token_id
+    );
+
+    event ApprovalForAll(
+        address indexed owner,
+        address indexed operator,
+        bool approved
+    );
+}
+
+// Interface for DeviceRegistry contract calls
+sol! {
+    interface IDeviceRegistry {
+        function get_device_owner(bytes32 device_id_hash) external view returns (address);
+        function get_device_owner_epoch(bytes32 device_id_hash) external view returns (uint256);
+    }
+}
+
+#[public]
+impl DeviceOperators {
+    /// Initialize the contract with the DeviceRegistry whose devices it approves operators for
+    pub fn initialize(&mut self, device_registry: Address) -> Result<(), Vec<u8>> {
+        if self.admin.get() != Address::ZERO {
+            return Err(b"Already initialized".to_vec());
+        }
+        self.admin.set(self.vm().msg_sender());
+        self.device_registry_address.set(device_registry);
+        Ok(())
+    }
+
+    // ========== Operator Approvals ==========
+
+    /// Approve (or with zero address, clear) an operator for a single device until it changes hands
+    /// (called by device owner or an operator approved for all of the owner's devices)
+    pub fn approve(&mut self, device_id_hash: B256, operator: Address) -> Result<(), Vec<u8>> {
+        let owner = self.registry_device_owner(device_id_hash)?;
+        if owner == Address::ZERO {
+            return Err(b"Device not registered".to_vec());
+        }
+        let sender = self.vm().msg_sender();
+        if sender != owner && !self.operator_approvals.getter(owner).getter(sender).get() {
+            return Err(b"Only device owner or operator can approve".to_vec());
+        }
+        if operator == owner {
+            return Err(b"Cannot approve device owner".to_vec());
+        }
+
+        let owner_epoch = self.registry_device_owner_epoch(device_id_hash)?;
+        let mut approval = self.device_approvals.setter(device_id_hash);
+        approval.operator.set(operator);
+        approval.owner_epoch.set(owner_epoch);
+
+        self.emit(Approval {
+            owner,
+            approved: operator,
+            token_id: device_id_hash.into(),
+        });
+
+        Ok(())
+    }
+
+    /// Approve or revoke an operator for all of the caller's devices
+    pub fn set_approval_for_all(&mut self, operator: Address, approved: bool) -> Result<(), Vec<u8>> {
+        let owner = self.vm().msg_sender();
+        if operator == Address::ZERO || operator == owner {
+            return Err(b"Invalid operator address".to_vec());
+        }
+
+        self.operator_approvals.setter(owner).setter(operator).set(approved);
+
+        self.emit(ApprovalForAll {
+            owner,
+            operator,
+            approved,
+        });
+
+        Ok(())
+    }
+
+    /// Get the operator approved for a single device (zero if none, or if the device has
+    /// changed hands since)
+    pub fn get_approved(&self, device_id_hash: B256) -> Result<Address, Vec<u8>> {
+        let approval = self.device_approvals.getter(device_id_hash);
+        let operator = approval.operator.get();
+        if operator == Address::ZERO || approval.owner_epoch.get() != self.registry_device_owner_epoch(device_id_hash)? {
+            return Ok(Address::ZERO);
+        }
+        Ok(operator)
+    }
+
+    /// Check if an operator is approved for all of an owner's devices
+    pub fn is_approved_for_all(&self, owner: Address, operator: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.operator_approvals.getter(owner).getter(operator).get())
+    }
+
+    /// Check if `account` is approved to manage a device held by `owner` in its ownership
+    /// epoch `owner_epoch` (the DeviceRegistry passes both, as it cannot be called back)
+    pub fn is_approved_operator(
+        &self,
+        owner: Address,
+        device_id_hash: B256,
+        owner_epoch: U256,
+        account: Address,
+    ) -> Result<bool, Vec<u8>> {
+        if account == Address::ZERO {
+            return Ok(false);
+        }
+        let approval = self.device_approvals.getter(device_id_hash);
+        if approval.operator.get() == account && approval.owner_epoch.get() == owner_epoch {
+            return Ok(true);
+        }
+        Ok(self.operator_approvals.getter(owner).getter(account).get())
+    }
+
+    // ========== Admin Functions ==========
+
+    /// Get contract owner
+    pub fn owner(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.admin.get())
+    }
+
+    /// Get the DeviceRegistry whose devices approvals apply to
+    pub fn device_registry(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.device_registry_address.get())
+    }
+
+    /// Update DeviceRegistry address
+    pub fn set_device_registry(&mut self, device_registry: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.device_registry_address.set(device_registry);
+        Ok(())
+    }
+}
+
+// Private helper functions
+impl DeviceOperators {
+    /// Emit an event; unlike `stylus_sdk::prelude::log` this cannot panic, which keeps
+    /// panic formatting out of the contract
+    fn emit<T: SolEvent>(&self, event: T) {
+        let mut topics = [alloy_sol_types::abi::token::WordToken::default(); 4];
+        let _ = event.encode_topics_raw(&mut topics);
+        let count = <T::TopicList as alloy_sol_types::TopicList>::COUNT;
+        let mut data = Vec::with_capacity(32 * count);
+        for topic in &topics[..count] {
+            data.extend_from_slice(topic.as_slice());
+        }
+        event.encode_data_to(&mut data);
+        self.vm().emit_log(&data, count);
+    }
+
+    /// Ensure only contract owner can call
+    fn only_owner(&self) -> Result<(), Vec<u8>> {
+        if self.vm().msg_sender() != self.admin.get() {
+            return Err(b"Only owner can call this function".to_vec());
+        }
+        Ok(())
+    }
+
+    /// Static call into the DeviceRegistry
+    fn registry_call(&self, calldata: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+        let registry = self.device_registry_address.get();
+        Ok(self.vm().static_call(&self, registry, calldata)?)
+    }
+
+    /// Get device owner from the DeviceRegistry (zero if unregistered)
+    fn registry_device_owner(&self, device_id_hash: B256) -> Result<Address, Vec<u8>> {
+        let response = self.registry_call(&IDeviceRegistry::get_device_ownerCall { device_id_hash }.abi_encode())?;
+        let (owner,) = <(Address,)>::abi_decode(&response, true)
+            .map_err(|_| b"Failed to decode owner response".to_vec())?;
+        Ok(owner)
+    }
+
+    /// Get the number of times a device has changed owner from the DeviceRegistry
+    fn registry_device_owner_epoch(&self, device_id_hash: B256) -> Result<U256, Vec<u8>> {
+        let response =
+            self.registry_call(&IDeviceRegistry::get_device_owner_epochCall { device_id_hash }.abi_encode())?;
+        let (epoch,) = <(U256,)>::abi_decode(&response, true)
+            .map_err(|_| b"Failed to decode epoch response".to_vec())?;
+        Ok(epoch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::{alloy_primitives::address, crypto, testing::*};
+
+    const ADMIN: Address = address!("0000000000000000000000000000000000000001");
+    const OWNER: Address = address!("0000000000000000000000000000000000000002");
+    const OPERATOR: Address = address!("0000000000000000000000000000000000000003");
+    const REGISTRY: Address = address!("0000000000000000000000000000000000000004");
+
+    /// Run `f` with `sender` as msg.sender
+    fn with_sender<R>(vm: &TestVM, sender: Address, f: impl FnOnce() -> R) -> R {
+        vm.set_sender(sender);
+        f()
+    }
+
+    /// Mock the DeviceRegistry owner and ownership epoch of a device
+    fn mock_device_owner(vm: &TestVM, device_id_hash: B256, owner: Address, epoch: u64) {
+        vm.mock_static_call(
+            REGISTRY,
+            IDeviceRegistry::get_device_ownerCall { device_id_hash }.abi_encode(),
+            Ok(owner.abi_encode()),
+        );
+        vm.mock_static_call(
+            REGISTRY,
+            IDeviceRegistry::get_device_owner_epochCall { device_id_hash }.abi_encode(),
+            Ok(U256::from(epoch).abi_encode()),
+        );
+    }
+
+    fn setup(vm: &TestVM) -> DeviceOperators {
+        let mut contract = DeviceOperators::from(vm);
+        with_sender(vm, ADMIN, || assert!(contract.initialize(REGISTRY).is_ok()));
+        contract
+    }
+
+    #[test]
+    fn test_device_approvals() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let meter = crypto::keccak(b"did:lcore:meter");
+        let stranger = address!("0000000000000000000000000000000000000005");
+
+        mock_device_owner(&vm, meter, Address::ZERO, 0);
+        with_sender(&vm, OWNER, || {
+            assert_eq!(contract.approve(meter, OPERATOR).unwrap_err(), b"Device not registered".to_vec());
+        });
+
+        mock_device_owner(&vm, meter, OWNER, 0);
+        with_sender(&vm, stranger, || {
+            let result = contract.approve(meter, OPERATOR);
+            assert_eq!(result.unwrap_err(), b"Only device owner or operator can approve".to_vec());
+        });
+        with_sender(&vm, OWNER, || {
+            assert_eq!(contract.approve(meter, OWNER).unwrap_err(), b"Cannot approve device owner".to_vec());
+            assert!(contract.approve(meter, OPERATOR).is_ok());
+        });
+        assert_eq!(contract.get_approved(meter).unwrap(), OPERATOR);
+        assert!(contract.is_approved_operator(OWNER, meter, U256::ZERO, OPERATOR).unwrap());
+        assert!(!contract.is_approved_operator(OWNER, meter, U256::ZERO, stranger).unwrap());
+
+        // Clearing with the zero address revokes the approval
+        with_sender(&vm, OWNER, || assert!(contract.approve(meter, Address::ZERO).is_ok()));
+        assert_eq!(contract.get_approved(meter).unwrap(), Address::ZERO);
+        assert!(!contract.is_approved_operator(OWNER, meter, U256::ZERO, OPERATOR).unwrap());
+        assert!(!contract.is_approved_operator(OWNER, meter, U256::ZERO, Address::ZERO).unwrap());
+    }
+
+    #[test]
+    fn test_approvals_lapse_on_transfer() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let meter = crypto::keccak(b"did:lcore:meter");
+        let buyer = address!("0000000000000000000000000000000000000005");
+
+        mock_device_owner(&vm, meter, OWNER, 0);
+        with_sender(&vm, OWNER, || assert!(contract.approve(meter, OPERATOR).is_ok()));
+
+        // The device changes hands and comes back: the old approval stays lapsed
+        mock_device_owner(&vm, meter, buyer, 1);
+        assert_eq!(contract.get_approved(meter).unwrap(), Address::ZERO);
+        assert!(!contract.is_approved_operator(buyer, meter, U256::from(1), OPERATOR).unwrap());
+        mock_device_owner(&vm, meter, OWNER, 2);
+        assert_eq!(contract.get_approved(meter).unwrap(), Address::ZERO);
+        assert!(!contract.is_approved_operator(OWNER, meter, U256::from(2), OPERATOR).unwrap());
+    }
+
+    #[test]
+    fn test_operators_for_all() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let meter = crypto::keccak(b"did:lcore:meter");
+        let integrator = address!("0000000000000000000000000000000000000005");
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.set_approval_for_all(Address::ZERO, true).is_err());
+            assert!(contract.set_approval_for_all(OWNER, true).is_err());
+            assert!(contract.set_approval_for_all(OPERATOR, true).is_ok());
+        });
+        assert!(contract.is_approved_for_all(OWNER, OPERATOR).unwrap());
+        assert!(contract.is_approved_operator(OWNER, meter, U256::from(3), OPERATOR).unwrap());
+
+        // An operator for all devices may approve per-device operators
+        mock_device_owner(&vm, meter, OWNER, 3);
+        with_sender(&vm, OPERATOR, || assert!(contract.approve(meter, integrator).is_ok()));
+        assert_eq!(contract.get_approved(meter).unwrap(), integrator);
+
+        with_sender(&vm, OWNER, || assert!(contract.set_approval_for_all(OPERATOR, false).is_ok()));
+        assert!(!contract.is_approved_for_all(OWNER, OPERATOR).unwrap());
+        assert!(!contract.is_approved_operator(OWNER, meter, U256::from(3), OPERATOR).unwrap());
+        with_sender(&vm, OPERATOR, || assert!(contract.approve(meter, OPERATOR).is_err()));
+    }
+
+    #[test]
+    fn test_admin_functions() {
+        let vm = TestVM::default();
+        let mut contract = setup(&vm);
+        let new_registry = address!("0000000000000000000000000000000000000006");
+
+        assert_eq!(contract.owner().unwrap(), ADMIN);
+        assert_eq!(contract.device_registry().unwrap(), REGISTRY);
+        with_sender(&vm, ADMIN, || {
+            assert!(contract.initialize(REGISTRY).is_err());
+        });
+
+        with_sender(&vm, OWNER, || {
+            assert!(contract.set_device_registry(new_registry).is_err());
+        });
+        with_sender(&vm, ADMIN, || {
+            assert!(contract.set_device_registry(new_registry).is_ok());
+        });
+        assert_eq!(contract.device_registry().unwrap(), new_registry);
+    }
+}
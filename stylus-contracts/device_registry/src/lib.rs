@@ -1,15 +1,23 @@
 // SPDX-License-Identifier: UNLICENSED
-#![cfg_attr(not(feature = "export-abi"), no_main)]
-// no_std removed for Stylus SDK 0.9
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_std)]
 
 #[macro_use]
 extern crate alloc;
 
+// Keep `core::fmt` panic formatting out of the deployed code
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
 use alloc::string::String;
 use alloc::vec::Vec;
-use alloy_sol_types::sol;
+use alloy_sol_types::{sol, SolCall, SolEvent};
 use stylus_sdk::{
-    alloy_primitives::{Address, B256, U256},
+    abi::Bytes,
+    alloy_primitives::{Address, B256, U256, U8},
     crypto, prelude::*,
 };
 
@@ -23,21 +31,43 @@ sol_storage! {
         mapping(bytes32 => string) device_types;         // Generic device type classification
         mapping(bytes32 => string) device_metadata;      // Generic metadata (JSON string)
         mapping(bytes32 => uint256) device_registered_at; // Registration timestamp
+        mapping(bytes32 => uint8) device_status;         // Lifecycle state (DEVICE_STATUS_*)
         
         /// Owner mappings (always wallet addresses)
         mapping(bytes32 => address) device_owners;       // device_id_hash -> owner address
         mapping(address => string[]) owner_devices;      // owner -> list of device_ids
-        
-        /// Access control mappings for data marketplace
-        mapping(address => mapping(address => bool)) access_permissions; // owner -> consumer -> allowed
-        mapping(address => mapping(address => uint256)) permission_expires; // owner -> consumer -> expiry timestamp
+        mapping(bytes32 => uint256) owner_device_index;  // device_id_hash -> index in owner_devices
         
         /// Admin and configuration
         address admin;
         uint256 total_devices;
+
+        /// Trusted rollup callers (CartesiDApp addresses executing vouchers)
+        mapping(address => bool) rollup_callers;
+
+        /// DeviceKeys contract that checks registration proofs of possession
+        address device_keys_address;
+
+        /// Metadata / DID document version per device (0 until the first update)
+        mapping(bytes32 => uint256) device_versions;
+
+        /// Ownership changes per device, so companions can drop state set by earlier owners
+        mapping(bytes32 => uint256) device_owner_epochs;
+
+        /// DeviceOperators contract holding ERC-721-style operator approvals
+        address device_operators_address;
     }
 }
 
+/// Device lifecycle states (registered devices start as provisioned)
+const DEVICE_STATUS_PROVISIONED: u8 = 0;
+const DEVICE_STATUS_ACTIVE: u8 = 1;
+const DEVICE_STATUS_SUSPENDED: u8 = 2;
+const DEVICE_STATUS_DECOMMISSIONED: u8 = 3;
+
+/// Device record returned by `get_device`: (owner, did_document, public_key, device_type, metadata, registered_at)
+type DeviceRecord = (Address, String, String, String, String, U256);
+
 // Simplified events
 sol! {
     event DeviceRegistered(
@@ -47,21 +77,57 @@ sol! {
         uint256 timestamp
     );
 
-    event AccessGranted(
-        address indexed owner,
-        address indexed consumer,
-        uint256 expires_at
+    event DeviceStatusChanged(
+        bytes32 indexed device_id_hash,
+        uint8 previous_status,
+        uint8 new_status
     );
 
-    event AccessRevoked(
-        address indexed owner,
-        address indexed consumer
+    event DeviceUpdated(
+        bytes32 indexed device_id_hash,
+        uint256 version,
+        bytes32 content_hash
+    );
+
+    event DeviceTransferred(
+        bytes32 indexed device_id_hash,
+        address indexed previous_owner,
+        address indexed new_owner
     );
 
     event OwnershipTransferred(
         address indexed previous_owner, 
         address indexed new_owner
     );
+
+    event RollupCallerUpdated(
+        address indexed rollup,
+        bool trusted
+    );
+}
+
+// Interfaces for DeviceKeys and DeviceOperators contract calls
+sol! {
+    interface IDeviceKeys {
+        function verify_registration(
+            string device_id,
+            address owner,
+            string public_key_hex,
+            bytes32 r,
+            bytes32 s
+        ) external view returns (bool);
+    }
+
+    interface IDeviceOperators {
+        function is_approved_operator(
+            address owner,
+            bytes32 device_id_hash,
+            uint256 owner_epoch,
+            address account
+        ) external view returns (bool);
+
+        function on_device_transfer(address from, address to, bytes32 device_id_hash) external;
+    }
 }
 
 #[public]
@@ -79,6 +145,7 @@ impl DeviceRegistry {
     // ========== Device Registration ==========
 
     /// Register a new device (simplified parameters)
+    /// `proof` is an ES-256 signature by `public_key_hex` of `DeviceKeys.registration_digest(device_id, caller)`
     #[payable]
     pub fn register_device(
         &mut self,
@@ -87,172 +154,129 @@ impl DeviceRegistry {
         public_key_hex: String,
         device_type: String,
         metadata: String, // Generic metadata as JSON string
+        proof: Bytes,
     ) -> Result<(), Vec<u8>> {
         // if self.is_paused.get() {               // REMOVED: Anti-decentralization
         //     return Err(b"Registry is paused".to_vec());
         // }
-        if device_id.is_empty() {
-            return Err(b"Device ID cannot be empty".to_vec());
-        }
-
-        let device_id_hash: B256 = crypto::keccak(device_id.as_bytes()).into();
-
-        if self.device_owners.getter(device_id_hash).get() != Address::ZERO {
-            return Err(b"Device already registered".to_vec());
-        }
 
         // Check registration fee
         // if self.vm().msg_value() < self.registry_fee.get() {  // REMOVED: Free Cartesi model
         //     return Err(b"Insufficient registration fee".to_vec());
         // }
 
-        // Store device information
-        self.device_dids.setter(device_id_hash).set_str(did_document);
-        self.device_public_keys.setter(device_id_hash).set_str(public_key_hex);
-        self.device_types.setter(device_id_hash).set_str(device_type.clone());
-        self.device_metadata.setter(device_id_hash).set_str(metadata);
-        
-        let timestamp = self.vm().block_timestamp();
-        self.device_registered_at.setter(device_id_hash).set(U256::from(timestamp));
-
         // Set ownership (ALWAYS wallet address)
         let owner = self.vm().msg_sender();
-        self.device_owners.setter(device_id_hash).set(owner);
-        self.owner_devices.setter(owner).grow().set_str(device_id);
-
-        // Update counters
-        let new_total = self.total_devices.get() + U256::from(1);
-        self.total_devices.set(new_total);
+        self.store_device(owner, device_id, did_document, public_key_hex, device_type, metadata, proof)
+    }
 
-        log(self.vm(), DeviceRegistered {
-            device_id_hash,
-            owner,
-            device_type,
-            timestamp: U256::from(timestamp),
-        });
+    /// Register a device on behalf of `owner` (called by a trusted rollup caller, e.g. the CartesiGateway)
+    /// `proof` is checked as for `register_device`, with `owner` in place of the caller
+    #[allow(clippy::too_many_arguments)]
+    pub fn register_device_for(
+        &mut self,
+        owner: Address,
+        device_id: String,
+        did_document: String,
+        public_key_hex: String,
+        device_type: String,
+        metadata: String,
+        proof: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        // Only trusted rollup callers can register on behalf of an owner
+        if !self.rollup_callers.getter(self.vm().msg_sender()).get() {
+            return Err(b"Unauthorized: only rollup can register".to_vec());
+        }
+        if owner == Address::ZERO {
+            return Err(b"Invalid owner address".to_vec());
+        }
 
-        Ok(())
+        self.store_device(owner, device_id, did_document, public_key_hex, device_type, metadata, proof)
     }
 
-    /// Register device from Cartesi rollup (called by rollup contract via voucher)
-    pub fn register_device_from_cartesi(&mut self, payload: Vec<u8>) -> Result<(), Vec<u8>> {
-        // if self.is_paused.get() {               // REMOVED: Anti-decentralization
-        //     return Err(b"Paused".to_vec());
-        // }
-
-        let s = String::from_utf8(payload).map_err(|_| b"Bad UTF-8".to_vec())?;
-        
-        let device_id = Self::get_val(&s, "device_id").ok_or(b"No device_id".to_vec())?;
-        let did_document = Self::get_val(&s, "did_document").ok_or(b"No did_document".to_vec())?;
-        let public_key = Self::get_val(&s, "public_key").unwrap_or("".to_string());
-        let device_type = Self::get_val(&s, "device_type").unwrap_or("iot".to_string());
-        
-        // Extract owner address from payload (NEW: Fix ownership tracking)
-        let owner_address_str = Self::get_val(&s, "owner_address")
-            .ok_or(b"No owner_address".to_vec())?;
-        
-        let owner = owner_address_str.parse::<Address>()
-            .map_err(|_| b"Invalid owner address".to_vec())?;
+    // ========== Device Ownership ==========
 
-        if device_id.is_empty() {
-            return Err(b"Empty ID".to_vec());
+    /// Transfer a device to a new owner (called by device owner or operator)
+    /// Operator approvals for the device lapse with the ownership change
+    pub fn transfer_device(&mut self, device_id_hash: B256, new_owner: Address) -> Result<(), Vec<u8>> {
+        if new_owner == Address::ZERO {
+            return Err(b"Invalid new owner address".to_vec());
         }
 
-        let hash: B256 = crypto::keccak(device_id.as_bytes()).into();
-
-        if self.device_owners.getter(hash).get() != Address::ZERO {
-            return Err(b"Exists".to_vec());
+        let previous_owner = self.only_device_manager(device_id_hash)?;
+        if new_owner == previous_owner {
+            return Err(b"Already device owner".to_vec());
         }
 
-        self.device_dids.setter(hash).set_str(did_document);
-        self.device_public_keys.setter(hash).set_str(public_key);
-        self.device_types.setter(hash).set_str(device_type.clone());
-        self.device_metadata.setter(hash).set_str("{}".to_string());
-        
-        let ts = self.vm().block_timestamp();
-        self.device_registered_at.setter(hash).set(U256::from(ts));
-
-        // Use extracted owner address instead of msg_sender
-        self.device_owners.setter(hash).set(owner);
-        self.owner_devices.setter(owner).grow().set_str(device_id);
-
-        self.total_devices.set(self.total_devices.get() + U256::from(1));
+        let device_id = self.remove_owner_device(previous_owner, device_id_hash);
+        self.device_owners.setter(device_id_hash).set(new_owner);
+        let epoch = self.device_owner_epochs.getter(device_id_hash).get() + U256::from(1);
+        self.device_owner_epochs.setter(device_id_hash).set(epoch);
+        self.add_owner_device(new_owner, device_id, device_id_hash);
 
-        log(self.vm(), DeviceRegistered {
-            device_id_hash: hash,
-            owner,
-            device_type,
-            timestamp: U256::from(ts),
+        self.announce_transfer(previous_owner, new_owner, device_id_hash)?;
+        self.emit(DeviceTransferred {
+            device_id_hash,
+            previous_owner,
+            new_owner,
         });
 
         Ok(())
     }
 
-    // ========== Access Control Functions ==========
+    /// Move a device through its lifecycle (called by device owner or operator)
+    /// Provisioned -> Active <-> Suspended, and any state -> Decommissioned (terminal)
+    pub fn set_device_status(&mut self, device_id_hash: B256, new_status: u8) -> Result<(), Vec<u8>> {
+        self.only_device_manager(device_id_hash)?;
 
-    /// Grant data access to a consumer (called by device owner)
-    pub fn grant_access(&mut self, consumer: Address, expires_at: U256) -> Result<(), Vec<u8>> {
-        if consumer == Address::ZERO {
-            return Err(b"Invalid consumer address".to_vec());
-        }
-
-        let owner = self.vm().msg_sender();
-        
-        // Set permission
-        self.access_permissions.setter(owner).setter(consumer).set(true);
-        
-        // Set expiration (0 = never expires)
-        if expires_at > U256::ZERO {
-            self.permission_expires.setter(owner).setter(consumer).set(expires_at);
+        let previous_status = self.device_status.getter(device_id_hash).get().saturating_to::<u8>();
+        if !Self::is_valid_status_transition(previous_status, new_status) {
+            return Err(b"Invalid status transition".to_vec());
         }
+        self.device_status.setter(device_id_hash).set(U8::from(new_status));
 
-        log(self.vm(), AccessGranted {
-            owner,
-            consumer,
-            expires_at,
+        self.emit(DeviceStatusChanged {
+            device_id_hash,
+            previous_status,
+            new_status,
         });
 
         Ok(())
     }
 
-    /// Revoke data access from a consumer (called by device owner)
-    pub fn revoke_access(&mut self, consumer: Address) -> Result<(), Vec<u8>> {
-        let owner = self.vm().msg_sender();
-        
-        self.access_permissions.setter(owner).setter(consumer).set(false);
-        self.permission_expires.setter(owner).setter(consumer).set(U256::ZERO);
-
-        log(self.vm(), AccessRevoked {
-            owner,
-            consumer,
-        });
-
+    /// Replace a device's metadata (JSON string), bumping its version (called by device owner or operator)
+    pub fn update_device_metadata(&mut self, device_id_hash: B256, metadata: String) -> Result<(), Vec<u8>> {
+        let content_hash = crypto::keccak(metadata.as_bytes());
+        self.only_device_manager(device_id_hash)?;
+        self.device_metadata.setter(device_id_hash).set_str(metadata);
+        self.bump_version(device_id_hash, content_hash);
         Ok(())
     }
 
-    /// Check if consumer has access to owner's data
-    pub fn has_access(&self, owner: Address, consumer: Address) -> Result<bool, Vec<u8>> {
-        let has_permission = self.access_permissions.getter(owner).getter(consumer).get();
-        
-        if !has_permission {
-            return Ok(false);
-        }
-
-        // Check expiration
-        let expires_at = self.permission_expires.getter(owner).getter(consumer).get();
-        if expires_at > U256::ZERO && U256::from(self.vm().block_timestamp()) > expires_at {
-            return Ok(false); // Permission expired
-        }
-
-        Ok(true)
+    /// Replace a device's DID document, bumping its version (called by device owner or operator)
+    pub fn update_did_document(&mut self, device_id_hash: B256, did_document: String) -> Result<(), Vec<u8>> {
+        let content_hash = crypto::keccak(did_document.as_bytes());
+        self.only_device_manager(device_id_hash)?;
+        self.device_dids.setter(device_id_hash).set_str(did_document);
+        self.bump_version(device_id_hash, content_hash);
+        Ok(())
     }
 
     // ========== Device Query Functions ==========
 
-    /// Check if a device is registered
+    /// Check if a device is registered and in service (not suspended or decommissioned)
     pub fn is_device_registered(&self, device_id_hash: B256) -> Result<bool, Vec<u8>> {
         let owner = self.device_owners.getter(device_id_hash).get();
-        Ok(owner != Address::ZERO)
+        if owner == Address::ZERO {
+            return Ok(false);
+        }
+        let status = self.device_status.getter(device_id_hash).get().saturating_to::<u8>();
+        Ok(status != DEVICE_STATUS_SUSPENDED && status != DEVICE_STATUS_DECOMMISSIONED)
+    }
+
+    /// Get device lifecycle status
+    pub fn get_device_status(&self, device_id_hash: B256) -> Result<u8, Vec<u8>> {
+        Ok(self.device_status.getter(device_id_hash).get().saturating_to::<u8>())
     }
 
     /// Get device owner address
@@ -260,7 +284,87 @@ impl DeviceRegistry {
         Ok(self.device_owners.getter(device_id_hash).get())
     }
 
-    /// Get devices owned by an address
+    /// Get the number of times a device has changed owner
+    pub fn get_device_owner_epoch(&self, device_id_hash: B256) -> Result<U256, Vec<u8>> {
+        Ok(self.device_owner_epochs.getter(device_id_hash).get())
+    }
+
+    /// Check if `account` owns a registered device or is an approved operator for it
+    /// (companions use this to let operators act for the owner)
+    pub fn is_device_manager(&self, device_id_hash: B256, account: Address) -> Result<bool, Vec<u8>> {
+        let owner = self.device_owners.getter(device_id_hash).get();
+        Ok(owner != Address::ZERO && self.manages_device(device_id_hash, owner, account))
+    }
+
+    /// Get device DID document
+    pub fn get_device_did(&self, device_id_hash: B256) -> Result<String, Vec<u8>> {
+        Ok(self.device_dids.getter(device_id_hash).get_string())
+    }
+
+    /// Get the public key a device was registered with (DeviceKeys tracks rotated keys)
+    pub fn get_device_public_key(&self, device_id_hash: B256) -> Result<String, Vec<u8>> {
+        Ok(self.device_public_keys.getter(device_id_hash).get_string())
+    }
+
+    /// Get device type
+    pub fn get_device_type(&self, device_id_hash: B256) -> Result<String, Vec<u8>> {
+        Ok(self.device_types.getter(device_id_hash).get_string())
+    }
+
+    /// Get device metadata (JSON string)
+    pub fn get_device_metadata(&self, device_id_hash: B256) -> Result<String, Vec<u8>> {
+        Ok(self.device_metadata.getter(device_id_hash).get_string())
+    }
+
+    /// Get the current metadata / DID document version (0 until the first update)
+    pub fn get_device_version(&self, device_id_hash: B256) -> Result<U256, Vec<u8>> {
+        Ok(self.device_versions.getter(device_id_hash).get())
+    }
+
+    /// Get device registration timestamp
+    pub fn get_device_registered_at(&self, device_id_hash: B256) -> Result<U256, Vec<u8>> {
+        Ok(self.device_registered_at.getter(device_id_hash).get())
+    }
+
+    /// Get full device record: (owner, did_document, public_key, device_type, metadata, registered_at)
+    pub fn get_device(&self, device_id_hash: B256) -> Result<DeviceRecord, Vec<u8>> {
+        Ok((
+            self.device_owners.getter(device_id_hash).get(),
+            self.device_dids.getter(device_id_hash).get_string(),
+            self.device_public_keys.getter(device_id_hash).get_string(),
+            self.device_types.getter(device_id_hash).get_string(),
+            self.device_metadata.getter(device_id_hash).get_string(),
+            self.device_registered_at.getter(device_id_hash).get(),
+        ))
+    }
+
+    /// Get number of devices owned by an address
+    pub fn get_owner_device_count(&self, owner: Address) -> Result<U256, Vec<u8>> {
+        Ok(U256::from(self.owner_devices.getter(owner).len()))
+    }
+
+    /// Get devices owned by an address (paginated), returning device IDs and their hashes
+    pub fn get_owner_devices(
+        &self,
+        owner: Address,
+        offset: U256,
+        limit: U256,
+    ) -> Result<(Vec<String>, Vec<B256>), Vec<u8>> {
+        let devices = self.owner_devices.getter(owner);
+        let (start, end) = Self::page_bounds(devices.len(), offset, limit);
+
+        let mut device_ids = Vec::with_capacity(end - start);
+        let mut device_id_hashes = Vec::with_capacity(end - start);
+        for i in start..end {
+            if let Some(device_id) = devices.getter(i) {
+                let device_id = device_id.get_string();
+                device_id_hashes.push(crypto::keccak(device_id.as_bytes()));
+                device_ids.push(device_id);
+            }
+        }
+        Ok((device_ids, device_id_hashes))
+    }
+
     // ========== Admin Functions ==========
 
     /// Get contract owner
@@ -276,30 +380,70 @@ impl DeviceRegistry {
         }
         let previous_owner = self.admin.get();
         self.admin.set(new_owner);
-        log(self.vm(), OwnershipTransferred { previous_owner, new_owner });
+        self.emit(OwnershipTransferred { previous_owner, new_owner });
         Ok(())
     }
 
-    /// Set registration fee
+    /// Add or remove a trusted rollup caller for `register_device_for`
+    pub fn set_rollup_caller(&mut self, rollup: Address, trusted: bool) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if rollup == Address::ZERO {
+            return Err(b"Invalid rollup address".to_vec());
+        }
+        self.rollup_callers.setter(rollup).set(trusted);
+        self.emit(RollupCallerUpdated { rollup, trusted });
+        Ok(())
+    }
+
+    /// Check if an address is a trusted rollup caller
+    pub fn is_rollup_caller(&self, rollup: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.rollup_callers.getter(rollup).get())
+    }
+
+    /// Get the DeviceKeys contract that checks registration proofs
+    pub fn device_keys(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.device_keys_address.get())
+    }
+
+    /// Update DeviceKeys address
+    pub fn set_device_keys(&mut self, device_keys: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.device_keys_address.set(device_keys);
+        Ok(())
+    }
+
+    /// Get the DeviceOperators contract holding operator approvals
+    pub fn device_operators(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.device_operators_address.get())
+    }
+
+    /// Update DeviceOperators address
+    pub fn set_device_operators(&mut self, device_operators: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.device_operators_address.set(device_operators);
+        Ok(())
+    }
+
+    // Set registration fee
     // pub fn set_registry_fee(&mut self, new_fee: U256) -> Result<(), Vec<u8>> {  // REMOVED: Free Cartesi model
     //     self.only_owner()?;
     //     self.registry_fee.set(new_fee);
     //     Ok(())
     // }
 
-    /// Get registration fee
+    // Get registration fee
     // pub fn registry_fee(&self) -> Result<U256, Vec<u8>> {  // REMOVED: Free Cartesi model
     //     Ok(self.registry_fee.get())
     // }
 
-    /// Pause/unpause contract
+    // Pause/unpause contract
     // pub fn set_paused(&mut self, paused: bool) -> Result<(), Vec<u8>> {  // REMOVED: Anti-decentralization
     //     self.only_owner()?;
     //     self.is_paused.set(paused);
     //     Ok(())
     // }
 
-    /// Check if contract is paused
+    // Check if contract is paused
     // pub fn is_paused(&self) -> Result<bool, Vec<u8>> {  // REMOVED: Anti-decentralization
     //     Ok(self.is_paused.get())
     // }
@@ -312,6 +456,20 @@ impl DeviceRegistry {
 
 // Private helper functions
 impl DeviceRegistry {
+    /// Emit an event; unlike `stylus_sdk::prelude::log` this cannot panic, which keeps
+    /// panic formatting out of the contract
+    fn emit<T: SolEvent>(&self, event: T) {
+        let mut topics = [alloy_sol_types::abi::token::WordToken::default(); 4];
+        let _ = event.encode_topics_raw(&mut topics);
+        let count = <T::TopicList as alloy_sol_types::TopicList>::COUNT;
+        let mut data = Vec::with_capacity(32 * count);
+        for topic in &topics[..count] {
+            data.extend_from_slice(topic.as_slice());
+        }
+        event.encode_data_to(&mut data);
+        self.vm().emit_log(&data, count);
+    }
+
     /// Ensure only contract owner can call
     fn only_owner(&self) -> Result<(), Vec<u8>> {
         if self.vm().msg_sender() != self.admin.get() {
@@ -320,69 +478,616 @@ impl DeviceRegistry {
         Ok(())
     }
 
-    /// Helper to extract a string from a JSON payload manually (simplified)
-    /// Minimal JSON value extractor
-    fn get_val(s: &str, k: &str) -> Option<String> {
-        let p = format!("\"{}\":\"", k);
-        let i = s.find(&p)? + p.len();
-        let e = s[i..].find('"')?;
-        Some(s[i..i + e].to_string())
+    /// Ensure the caller owns or is an approved operator of a device that has not been
+    /// decommissioned, returning the owner
+    fn only_device_manager(&self, device_id_hash: B256) -> Result<Address, Vec<u8>> {
+        let owner = self.device_owners.getter(device_id_hash).get();
+        if owner == Address::ZERO {
+            return Err(b"Device not registered".to_vec());
+        }
+        if !self.manages_device(device_id_hash, owner, self.vm().msg_sender()) {
+            return Err(b"Only device owner or operator can call this function".to_vec());
+        }
+        if self.device_status.getter(device_id_hash).get().saturating_to::<u8>() == DEVICE_STATUS_DECOMMISSIONED {
+            return Err(b"Device decommissioned".to_vec());
+        }
+        Ok(owner)
+    }
+
+    /// Check whether `account` is the owner or, per DeviceOperators, an operator approved
+    /// for the device or for all of the owner's devices
+    fn manages_device(&self, device_id_hash: B256, owner: Address, account: Address) -> bool {
+        if account == owner {
+            return true;
+        }
+        let device_operators = self.device_operators_address.get();
+        if device_operators == Address::ZERO {
+            return false;
+        }
+        if account == device_operators {
+            return true;
+        }
+        let calldata = IDeviceOperators::is_approved_operatorCall {
+            owner,
+            device_id_hash,
+            owner_epoch: self.device_owner_epochs.getter(device_id_hash).get(),
+            account,
+        }
+        .abi_encode();
+        self.vm()
+            .static_call(&self, device_operators, &calldata)
+            .is_ok_and(|response| response.len() == 32 && U256::from_be_slice(&response) == U256::from(1))
+    }
+
+    /// Tell DeviceToken about an ownership change it did not make itself
+    fn announce_transfer(&mut self, from: Address, to: Address, device_id_hash: B256) -> Result<(), Vec<u8>> {
+        let device_token = self.device_operators_address.get();
+        if device_token == Address::ZERO || self.vm().msg_sender() == device_token {
+            return Ok(());
+        }
+        let calldata = IDeviceOperators::on_device_transferCall { from, to, device_id_hash }.abi_encode();
+        if self.vm().call(&self, device_token, &calldata).is_err() {
+            return Err(b"Device token update failed".to_vec());
+        }
+        Ok(())
+    }
+
+    /// Increment a device's version and announce the new content
+    fn bump_version(&mut self, device_id_hash: B256, content_hash: B256) {
+        let version = self.device_versions.getter(device_id_hash).get() + U256::from(1);
+        self.device_versions.setter(device_id_hash).set(version);
+        self.emit(DeviceUpdated { device_id_hash, version, content_hash });
+    }
+
+    /// Check whether a lifecycle transition is allowed
+    fn is_valid_status_transition(from: u8, to: u8) -> bool {
+        matches!(
+            (from, to),
+            (DEVICE_STATUS_PROVISIONED, DEVICE_STATUS_ACTIVE)
+                | (DEVICE_STATUS_ACTIVE, DEVICE_STATUS_SUSPENDED)
+                | (DEVICE_STATUS_SUSPENDED, DEVICE_STATUS_ACTIVE)
+                | (
+                    DEVICE_STATUS_PROVISIONED | DEVICE_STATUS_ACTIVE | DEVICE_STATUS_SUSPENDED,
+                    DEVICE_STATUS_DECOMMISSIONED
+                )
+        )
+    }
+
+    /// Append a device to an owner's list and record its index
+    fn add_owner_device(&mut self, owner: Address, device_id: String, device_id_hash: B256) {
+        let mut devices = self.owner_devices.setter(owner);
+        let index = devices.len();
+        devices.grow().set_str(device_id);
+        self.owner_device_index.setter(device_id_hash).set(U256::from(index));
+    }
+
+    /// Swap-remove a device from an owner's list, returning its device ID
+    fn remove_owner_device(&mut self, owner: Address, device_id_hash: B256) -> String {
+        let index = self.owner_device_index.getter(device_id_hash).get().saturating_to::<usize>();
+        let mut devices = self.owner_devices.setter(owner);
+        let last = devices.len() - 1;
+
+        let device_id = devices.getter(index).map(|id| id.get_string()).unwrap_or_default();
+        if index != last {
+            let moved = devices.getter(last).map(|id| id.get_string()).unwrap_or_default();
+            let moved_hash = crypto::keccak(moved.as_bytes());
+            if let Some(mut slot) = devices.setter(index) {
+                slot.set_str(moved);
+            }
+            self.owner_device_index.setter(moved_hash).set(U256::from(index));
+        }
+        devices.erase_last();
+        self.owner_device_index.delete(device_id_hash);
+
+        device_id
+    }
+
+    /// Clamp an (offset, limit) page request to `[start, end)` indices of a list of length `len`
+    fn page_bounds(len: usize, offset: U256, limit: U256) -> (usize, usize) {
+        let start = usize::try_from(offset).unwrap_or(usize::MAX).min(len);
+        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+        (start, start.saturating_add(limit).min(len))
+    }
+
+    /// Check with DeviceKeys that `proof`, a 64-byte (r, s) ES-256 signature, shows
+    /// possession of the key registered for `device_id`
+    fn check_registration_proof(
+        &self,
+        owner: Address,
+        device_id: &str,
+        public_key_hex: &str,
+        proof: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        let device_keys = self.device_keys_address.get();
+        if device_keys == Address::ZERO {
+            return Err(b"Device keys not configured".to_vec());
+        }
+        if proof.len() != 64 {
+            return Err(b"Invalid proof length".to_vec());
+        }
+        let calldata = IDeviceKeys::verify_registrationCall {
+            device_id: device_id.into(),
+            owner,
+            public_key_hex: public_key_hex.into(),
+            r: B256::from_slice(&proof[..32]),
+            s: B256::from_slice(&proof[32..]),
+        }
+        .abi_encode();
+        // verify_registration returns an ABI-encoded bool; anything but `true` is a rejection
+        let valid = self
+            .vm()
+            .static_call(&self, device_keys, &calldata)
+            .is_ok_and(|response| response.len() == 32 && U256::from_be_slice(&response) == U256::from(1));
+        if !valid {
+            return Err(b"Invalid proof of possession".to_vec());
+        }
+        Ok(())
+    }
+
+    /// Store a new device record owned by `owner` once its proof of possession checks out
+    #[allow(clippy::too_many_arguments)]
+    fn store_device(
+        &mut self,
+        owner: Address,
+        device_id: String,
+        did_document: String,
+        public_key_hex: String,
+        device_type: String,
+        metadata: String,
+        proof: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        if device_id.is_empty() {
+            return Err(b"Device ID cannot be empty".to_vec());
+        }
+
+        let device_id_hash: B256 = crypto::keccak(device_id.as_bytes());
+
+        if self.device_owners.getter(device_id_hash).get() != Address::ZERO {
+            return Err(b"Device already registered".to_vec());
+        }
+        self.check_registration_proof(owner, &device_id, &public_key_hex, proof)?;
+
+        // Store device information
+        self.device_dids.setter(device_id_hash).set_str(did_document);
+        self.device_public_keys.setter(device_id_hash).set_str(public_key_hex);
+        self.device_types.setter(device_id_hash).set_str(&device_type);
+        self.device_metadata.setter(device_id_hash).set_str(metadata);
+
+        let timestamp = self.vm().block_timestamp();
+        self.device_registered_at.setter(device_id_hash).set(U256::from(timestamp));
+
+        self.device_owners.setter(device_id_hash).set(owner);
+        self.add_owner_device(owner, device_id, device_id_hash);
+
+        // Update counters
+        let new_total = self.total_devices.get() + U256::from(1);
+        self.total_devices.set(new_total);
+
+        self.announce_transfer(Address::ZERO, owner, device_id_hash)?;
+        self.emit(DeviceRegistered {
+            device_id_hash,
+            owner,
+            device_type,
+            timestamp: U256::from(timestamp),
+        });
+
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use stylus_sdk::{test_helpers, alloy_primitives::{address, U256}};
+    use alloy_sol_types::SolValue;
+    use stylus_sdk::{alloy_primitives::{address, U256}, testing::*};
+
+    const DEVICE_KEYS: Address = address!("0000000000000000000000000000000000000005");
+    const DEVICE_OPERATORS: Address = address!("0000000000000000000000000000000000000006");
+
+    /// Run `f` with `sender` as msg.sender
+    fn with_sender<R>(vm: &TestVM, sender: Address, f: impl FnOnce() -> R) -> R {
+        vm.set_sender(sender);
+        f()
+    }
+
+    /// Run `f` with `value` attached as msg.value
+    fn with_value<R>(vm: &TestVM, value: U256, f: impl FnOnce() -> R) -> R {
+        vm.set_value(value);
+        let result = f();
+        vm.set_value(U256::ZERO);
+        result
+    }
+
+    /// Initialize the registry with the caller as admin and a mocked DeviceKeys
+    fn initialize_with_device_keys(contract: &mut DeviceRegistry) {
+        let _ = contract.initialize();
+        assert!(contract.set_device_keys(DEVICE_KEYS).is_ok());
+    }
+
+    /// Test proof for registering `device_id` to `owner`, which the mocked DeviceKeys
+    /// accepts for `public_key` when `valid`
+    fn mock_registration_proof(vm: &TestVM, device_id: &str, owner: Address, public_key: &str, valid: bool) -> Bytes {
+        let r = stylus_sdk::crypto::keccak(device_id.as_bytes());
+        let s = B256::left_padding_from(owner.as_slice());
+        let calldata = IDeviceKeys::verify_registrationCall {
+            device_id: device_id.into(),
+            owner,
+            public_key_hex: public_key.into(),
+            r,
+            s,
+        }
+        .abi_encode();
+        vm.mock_static_call(DEVICE_KEYS, calldata, Ok(valid.abi_encode()));
+        [r, s].concat().into()
+    }
+
+    /// Register `device_id` for `owner` (the current sender) with an accepted proof
+    fn register_test_device(vm: &TestVM, contract: &mut DeviceRegistry, device_id: &str, owner: Address) -> Result<(), Vec<u8>> {
+        let proof = mock_registration_proof(vm, device_id, owner, "key", true);
+        contract.register_device(
+            device_id.into(),
+            "doc".into(),
+            "key".into(),
+            "environmental_sensor".into(),
+            "{}".into(),
+            proof,
+        )
+    }
 
     #[test]
     fn test_device_registration() {
-        let mut contract = DeviceRegistry::new();
+        let vm = TestVM::default();
+        let mut contract = DeviceRegistry::from(&vm);
         let owner_address = address!("0000000000000000000000000000000000000001");
 
-        test_helpers::with_sender(owner_address, || {
+        with_sender(&vm, owner_address, || {
             // Initialize contract
-            let _ = contract.initialize();
+            initialize_with_device_keys(&mut contract);
 
             // Register device
-            test_helpers::with_value(U256::from(100), || {
+            let proof = mock_registration_proof(&vm, "did:lcore:test-device", owner_address, "test_public_key", true);
+            with_value(&vm, U256::from(100), || {
                 let result = contract.register_device(
                     "did:lcore:test-device".into(),
                     "test_did_document".into(),
                     "test_public_key".into(),
                     "environmental_sensor".into(),
                     "{}".into(), // empty metadata
+                    proof,
                 );
                 assert!(result.is_ok(), "Device registration should succeed");
             });
 
             // Check device is registered
-            let device_hash = stylus_sdk::crypto::keccak(b"did:lcore:test-device").into();
+            let device_hash = stylus_sdk::crypto::keccak(b"did:lcore:test-device");
+            assert!(contract.is_device_registered(device_hash).unwrap());
+            assert_eq!(contract.get_device_owner(device_hash).unwrap(), owner_address);
+
+            // Check full device record
+            let (owner, did, public_key, device_type, metadata, _) = contract.get_device(device_hash).unwrap();
+            assert_eq!(owner, owner_address);
+            assert_eq!(did, "test_did_document");
+            assert_eq!(public_key, "test_public_key");
+            assert_eq!(device_type, "environmental_sensor");
+            assert_eq!(metadata, "{}");
+            assert_eq!(contract.get_device_public_key(device_hash).unwrap(), "test_public_key");
+        });
+    }
+
+    #[test]
+    fn test_registration_requires_proof_of_possession() {
+        let vm = TestVM::default();
+        let mut contract = DeviceRegistry::from(&vm);
+        let owner_address = address!("0000000000000000000000000000000000000001");
+        let device_hash = stylus_sdk::crypto::keccak(b"did:lcore:pop");
+        let register = |contract: &mut DeviceRegistry, proof: Bytes| {
+            contract.register_device(
+                "did:lcore:pop".into(),
+                "doc".into(),
+                "key".into(),
+                "environmental_sensor".into(),
+                "{}".into(),
+                proof,
+            )
+        };
+
+        with_sender(&vm, owner_address, || {
+            let _ = contract.initialize();
+
+            // Proofs cannot be checked until DeviceKeys is configured
+            let proof = mock_registration_proof(&vm, "did:lcore:pop", owner_address, "key", false);
+            assert_eq!(register(&mut contract, proof.clone()).unwrap_err(), b"Device keys not configured".to_vec());
+            assert!(contract.set_device_keys(DEVICE_KEYS).is_ok());
+            assert_eq!(contract.device_keys().unwrap(), DEVICE_KEYS);
+
+            // Rejected proofs store nothing
+            assert_eq!(register(&mut contract, proof).unwrap_err(), b"Invalid proof of possession".to_vec());
+            let short_proof = Bytes::from(vec![1u8; 63]);
+            assert_eq!(register(&mut contract, short_proof).unwrap_err(), b"Invalid proof length".to_vec());
+            assert!(!contract.is_device_registered(device_hash).unwrap());
+            assert_eq!(contract.total_devices().unwrap(), U256::ZERO);
+
+            let proof = mock_registration_proof(&vm, "did:lcore:pop", owner_address, "key", true);
+            assert!(register(&mut contract, proof).is_ok());
             assert!(contract.is_device_registered(device_hash).unwrap());
+        });
+
+        // Only the admin configures DeviceKeys
+        with_sender(&vm, address!("0000000000000000000000000000000000000002"), || {
+            assert!(contract.set_device_keys(Address::ZERO).is_err());
+        });
+    }
+
+    #[test]
+    fn test_register_device_for_requires_rollup_caller() {
+        let vm = TestVM::default();
+        let mut contract = DeviceRegistry::from(&vm);
+        let admin_address = address!("0000000000000000000000000000000000000001");
+        let rollup_address = address!("0000000000000000000000000000000000000003");
+        let owner_address = address!("0000000000000000000000000000000000000002");
+        let proof = mock_registration_proof(&vm, "did:lcore:rollup-device", owner_address, "key", true);
+        let register = |contract: &mut DeviceRegistry| {
+            contract.register_device_for(
+                owner_address,
+                "did:lcore:rollup-device".into(),
+                "doc".into(),
+                "key".into(),
+                "iot".into(),
+                "{}".into(),
+                proof.clone(),
+            )
+        };
+
+        with_sender(&vm, admin_address, || {
+            initialize_with_device_keys(&mut contract);
+
+            // Untrusted callers are rejected
+            assert!(register(&mut contract).is_err(), "Untrusted caller should be rejected");
+
+            // Only the admin manages rollup callers
+            assert!(contract.set_rollup_caller(rollup_address, true).is_ok());
+            assert!(contract.is_rollup_caller(rollup_address).unwrap());
+        });
+
+        with_sender(&vm, rollup_address, || {
+            assert!(contract.set_rollup_caller(rollup_address, false).is_err());
+
+            assert!(register(&mut contract).is_ok(), "Trusted rollup should register device");
+            let device_hash = stylus_sdk::crypto::keccak(b"did:lcore:rollup-device");
             assert_eq!(contract.get_device_owner(device_hash).unwrap(), owner_address);
         });
+
+        with_sender(&vm, admin_address, || {
+            assert!(contract.set_rollup_caller(rollup_address, false).is_ok());
+            assert!(!contract.is_rollup_caller(rollup_address).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_owner_device_enumeration() {
+        let vm = TestVM::default();
+        let mut contract = DeviceRegistry::from(&vm);
+        let owner_address = address!("0000000000000000000000000000000000000001");
+
+        with_sender(&vm, owner_address, || {
+            initialize_with_device_keys(&mut contract);
+            for device_id in ["did:lcore:a", "did:lcore:b", "did:lcore:c"] {
+                let result = register_test_device(&vm, &mut contract, device_id, owner_address);
+                assert!(result.is_ok(), "Device registration should succeed");
+            }
+
+            assert_eq!(contract.get_owner_device_count(owner_address).unwrap(), U256::from(3));
+
+            let (ids, hashes) = contract
+                .get_owner_devices(owner_address, U256::from(1), U256::from(10))
+                .unwrap();
+            assert_eq!(ids, vec![String::from("did:lcore:b"), String::from("did:lcore:c")]);
+            assert_eq!(hashes[0], stylus_sdk::crypto::keccak(b"did:lcore:b"));
+
+            // Offset past the end yields an empty page
+            let (ids, _) = contract
+                .get_owner_devices(owner_address, U256::from(5), U256::from(10))
+                .unwrap();
+            assert!(ids.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_page_bounds() {
+        assert_eq!(DeviceRegistry::page_bounds(5, U256::from(1), U256::from(2)), (1, 3));
+        assert_eq!(DeviceRegistry::page_bounds(5, U256::from(4), U256::from(10)), (4, 5));
+        assert_eq!(DeviceRegistry::page_bounds(5, U256::from(9), U256::from(1)), (5, 5));
+        assert_eq!(DeviceRegistry::page_bounds(5, U256::ZERO, U256::MAX), (0, 5));
+    }
+
+    #[test]
+    fn test_device_transfer() {
+        let vm = TestVM::default();
+        let mut contract = DeviceRegistry::from(&vm);
+        let owner_address = address!("0000000000000000000000000000000000000001");
+        let buyer_address = address!("0000000000000000000000000000000000000002");
+        let device_hash = stylus_sdk::crypto::keccak(b"did:lcore:a");
+
+        with_sender(&vm, owner_address, || {
+            initialize_with_device_keys(&mut contract);
+            for device_id in ["did:lcore:a", "did:lcore:b", "did:lcore:c"] {
+                let result = register_test_device(&vm, &mut contract, device_id, owner_address);
+                assert!(result.is_ok(), "Device registration should succeed");
+            }
+
+            let result = contract.transfer_device(device_hash, buyer_address);
+            assert!(result.is_ok(), "Device transfer should succeed");
+            assert_eq!(contract.get_device_owner(device_hash).unwrap(), buyer_address);
+            assert_eq!(contract.get_device_owner_epoch(device_hash).unwrap(), U256::from(1));
+
+            // Last device is swapped into the freed slot
+            let (ids, _) = contract
+                .get_owner_devices(owner_address, U256::ZERO, U256::from(10))
+                .unwrap();
+            assert_eq!(ids, vec![String::from("did:lcore:c"), String::from("did:lcore:b")]);
+
+            // Previous owner can no longer transfer the device
+            assert!(contract.transfer_device(device_hash, owner_address).is_err());
+        });
+
+        with_sender(&vm, buyer_address, || {
+            let (ids, _) = contract
+                .get_owner_devices(buyer_address, U256::ZERO, U256::from(10))
+                .unwrap();
+            assert_eq!(ids, vec![String::from("did:lcore:a")]);
+
+            // Transferring back empties the buyer's list
+            assert!(contract.transfer_device(device_hash, owner_address).is_ok());
+            assert_eq!(contract.get_owner_device_count(buyer_address).unwrap(), U256::ZERO);
+        });
     }
 
     #[test]
-    fn test_access_control() {
-        let mut contract = DeviceRegistry::new();
+    fn test_device_lifecycle() {
+        let vm = TestVM::default();
+        let mut contract = DeviceRegistry::from(&vm);
         let owner_address = address!("0000000000000000000000000000000000000001");
-        let consumer_address = address!("0000000000000000000000000000000000000002");
+        let buyer_address = address!("0000000000000000000000000000000000000002");
+        let device_hash = stylus_sdk::crypto::keccak(b"did:lcore:lifecycle");
+
+        with_sender(&vm, owner_address, || {
+            initialize_with_device_keys(&mut contract);
+            let result = register_test_device(&vm, &mut contract, "did:lcore:lifecycle", owner_address);
+            assert!(result.is_ok(), "Device registration should succeed");
+            assert_eq!(contract.get_device_status(device_hash).unwrap(), DEVICE_STATUS_PROVISIONED);
 
-        test_helpers::with_sender(owner_address, || {
-            // Grant access
-            let result = contract.grant_access(consumer_address, U256::ZERO);
-            assert!(result.is_ok(), "Access grant should succeed");
+            assert!(contract.set_device_status(device_hash, DEVICE_STATUS_ACTIVE).is_ok());
+            assert!(contract.set_device_status(device_hash, DEVICE_STATUS_SUSPENDED).is_ok());
+            assert!(!contract.is_device_registered(device_hash).unwrap());
 
-            // Check access
-            assert!(contract.has_access(owner_address, consumer_address).unwrap());
+            assert!(contract.set_device_status(device_hash, DEVICE_STATUS_ACTIVE).is_ok());
+            assert!(contract.is_device_registered(device_hash).unwrap());
 
-            // Revoke access
-            let result = contract.revoke_access(consumer_address);
-            assert!(result.is_ok(), "Access revoke should succeed");
+            assert!(contract.set_device_status(device_hash, DEVICE_STATUS_DECOMMISSIONED).is_ok());
+            assert!(!contract.is_device_registered(device_hash).unwrap());
+
+            // Decommissioned is terminal and cannot be transferred
+            assert!(contract.set_device_status(device_hash, DEVICE_STATUS_ACTIVE).is_err());
+            assert!(contract.transfer_device(device_hash, buyer_address).is_err());
+        });
 
-            // Check access revoked
-            assert!(!contract.has_access(owner_address, consumer_address).unwrap());
+        // Only the owner or an approved operator moves a device through its lifecycle
+        with_sender(&vm, buyer_address, || {
+            assert!(contract.set_device_status(device_hash, DEVICE_STATUS_DECOMMISSIONED).is_err());
         });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_device_updates() {
+        let vm = TestVM::default();
+        let mut contract = DeviceRegistry::from(&vm);
+        let owner_address = address!("0000000000000000000000000000000000000001");
+        let other_address = address!("0000000000000000000000000000000000000002");
+        let device_hash = stylus_sdk::crypto::keccak(b"did:lcore:updates");
+
+        with_sender(&vm, owner_address, || {
+            initialize_with_device_keys(&mut contract);
+            let result = register_test_device(&vm, &mut contract, "did:lcore:updates", owner_address);
+            assert!(result.is_ok(), "Device registration should succeed");
+            assert_eq!(contract.get_device_version(device_hash).unwrap(), U256::ZERO);
+
+            let metadata = String::from(r#"{"location":"roof"}"#);
+            assert!(contract.update_device_metadata(device_hash, metadata.clone()).is_ok());
+            assert_eq!(contract.get_device_metadata(device_hash).unwrap(), metadata);
+            assert_eq!(contract.get_device_version(device_hash).unwrap(), U256::from(1));
+
+            assert!(contract.update_did_document(device_hash, "doc-v2".into()).is_ok());
+            assert_eq!(contract.get_device_did(device_hash).unwrap(), "doc-v2");
+            assert_eq!(contract.get_device_version(device_hash).unwrap(), U256::from(2));
+
+            let logs = vm.get_emitted_logs();
+            let (topics, data) = logs.last().unwrap();
+            assert_eq!(topics[0], DeviceUpdated::SIGNATURE_HASH);
+            let event = DeviceUpdated::decode_raw_log(topics.clone(), data, true).unwrap();
+            assert_eq!(event.version, U256::from(2));
+            assert_eq!(event.content_hash, stylus_sdk::crypto::keccak(b"doc-v2"));
+
+            assert!(contract.update_device_metadata(B256::ZERO, "{}".into()).is_err());
+        });
+
+        // Only the owner or an approved operator can update, and versions do not move on failure
+        with_sender(&vm, other_address, || {
+            assert!(contract.update_device_metadata(device_hash, "{}".into()).is_err());
+            assert!(contract.update_did_document(device_hash, "doc".into()).is_err());
+            assert_eq!(contract.get_device_version(device_hash).unwrap(), U256::from(2));
+        });
+
+        // Decommissioned devices are frozen
+        with_sender(&vm, owner_address, || {
+            assert!(contract.set_device_status(device_hash, DEVICE_STATUS_DECOMMISSIONED).is_ok());
+            assert!(contract.update_device_metadata(device_hash, "{}".into()).is_err());
+        });
+    }
+
+    #[test]
+    fn test_operator_approvals() {
+        let vm = TestVM::default();
+        let mut contract = DeviceRegistry::from(&vm);
+        let owner_address = address!("0000000000000000000000000000000000000001");
+        let operator_address = address!("0000000000000000000000000000000000000002");
+        let buyer_address = address!("0000000000000000000000000000000000000003");
+        let device_hash = stylus_sdk::crypto::keccak(b"did:lcore:operated");
+        let mock_operator = |owner: Address, epoch: u64, approved: bool| {
+            let calldata = IDeviceOperators::is_approved_operatorCall {
+                owner,
+                device_id_hash: device_hash,
+                owner_epoch: U256::from(epoch),
+                account: operator_address,
+            }
+            .abi_encode();
+            vm.mock_static_call(DEVICE_OPERATORS, calldata, Ok(approved.abi_encode()));
+        };
+
+        with_sender(&vm, owner_address, || {
+            initialize_with_device_keys(&mut contract);
+            let result = register_test_device(&vm, &mut contract, "did:lcore:operated", owner_address);
+            assert!(result.is_ok(), "Device registration should succeed");
+        });
+        assert!(contract.is_device_manager(device_hash, owner_address).unwrap());
+        assert!(!contract.is_device_manager(B256::ZERO, owner_address).unwrap());
+
+        // Operators are only recognized once DeviceOperators is configured
+        mock_operator(owner_address, 0, true);
+        assert!(!contract.is_device_manager(device_hash, operator_address).unwrap());
+        with_sender(&vm, operator_address, || {
+            let result = contract.update_device_metadata(device_hash, "{}".into());
+            assert_eq!(result.unwrap_err(), b"Only device owner or operator can call this function".to_vec());
+            assert!(contract.set_device_operators(DEVICE_OPERATORS).is_err());
+        });
+        with_sender(&vm, owner_address, || assert!(contract.set_device_operators(DEVICE_OPERATORS).is_ok()));
+        assert_eq!(contract.device_operators().unwrap(), DEVICE_OPERATORS);
+        assert!(contract.is_device_manager(device_hash, operator_address).unwrap());
+
+        // An approved operator manages the device on the owner's behalf
+        with_sender(&vm, operator_address, || {
+            assert!(contract.update_device_metadata(device_hash, r#"{"site":"b7"}"#.into()).is_ok());
+            assert!(contract.update_did_document(device_hash, "doc-v2".into()).is_ok());
+            assert!(contract.set_device_status(device_hash, DEVICE_STATUS_ACTIVE).is_ok());
+            assert!(contract.transfer_device(device_hash, buyer_address).is_ok());
+        });
+        assert_eq!(contract.get_device_version(device_hash).unwrap(), U256::from(2));
+        assert_eq!(contract.get_device_owner(device_hash).unwrap(), buyer_address);
+
+        // Approvals are asked for the new ownership epoch, so they lapse with the transfer
+        mock_operator(buyer_address, 1, false);
+        assert!(!contract.is_device_manager(device_hash, operator_address).unwrap());
+        with_sender(&vm, operator_address, || {
+            assert!(contract.update_device_metadata(device_hash, "{}".into()).is_err());
+        });
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        assert!(DeviceRegistry::is_valid_status_transition(DEVICE_STATUS_PROVISIONED, DEVICE_STATUS_ACTIVE));
+        assert!(DeviceRegistry::is_valid_status_transition(DEVICE_STATUS_SUSPENDED, DEVICE_STATUS_DECOMMISSIONED));
+        assert!(!DeviceRegistry::is_valid_status_transition(DEVICE_STATUS_PROVISIONED, DEVICE_STATUS_SUSPENDED));
+        assert!(!DeviceRegistry::is_valid_status_transition(DEVICE_STATUS_ACTIVE, DEVICE_STATUS_ACTIVE));
+        assert!(!DeviceRegistry::is_valid_status_transition(DEVICE_STATUS_DECOMMISSIONED, DEVICE_STATUS_ACTIVE));
+        assert!(!DeviceRegistry::is_valid_status_transition(DEVICE_STATUS_ACTIVE, 7));
+    }
+}
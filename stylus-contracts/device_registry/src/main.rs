@@ -1,9 +1,10 @@
-#![cfg_attr(not(feature = "export-abi"), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
 
 #[cfg(feature = "export-abi")]
 fn main() {
     // ABI export handled by cargo stylus; no-op.
 }
 
-#[cfg(not(feature = "export-abi"))]
-fn main() {} 
\ No newline at end of file
+#[cfg(not(any(test, feature = "export-abi")))]
+#[no_mangle]
+pub extern "C" fn main() {} 
\ No newline at end of file
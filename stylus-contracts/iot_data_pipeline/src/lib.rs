@@ -1,11 +1,18 @@
-#![cfg_attr(not(feature = "export-abi"), no_main)]
-// no_std removed for Stylus SDK 0.9 compatibility
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_std)]
 
 #[macro_use]
 extern crate alloc;
 
+// Keep `core::fmt` panic formatting out of the deployed code
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
 use alloc::vec::Vec;
-use alloy_sol_types::{sol, SolCall, SolValue};
+use alloy_sol_types::{sol, SolCall, SolEvent, SolValue};
 use stylus_sdk::{
     alloy_primitives::{Address, B256, U256},
     crypto::keccak,
@@ -49,6 +56,9 @@ sol_storage! {
         
         /// Counters
         uint256 total_submissions;
+
+        /// DeviceAccess contract holding owner-wide and device-scoped grants
+        address device_access_address;
     }
 
     /// Simplified data submission record
@@ -61,15 +71,32 @@ sol_storage! {
     }
 }
 
-/// Interface for DeviceRegistry contract calls
+// Interface for DeviceRegistry contract calls
 sol! {
     interface IDeviceRegistry {
         function is_device_registered(bytes32 device_id_hash) external view returns (bool);
         function get_device_owner(bytes32 device_id_hash) external view returns (address);
+    }
+}
+
+// Interface for DeviceAccess contract calls
+sol! {
+    interface IDeviceAccess {
         function has_access(address owner, address consumer) external view returns (bool);
+        function has_access_scope(address owner, address consumer, uint8 scope) external view returns (bool);
+        function has_device_access_scope(bytes32 device_id_hash, address consumer, uint8 scope) external view returns (bool);
     }
 }
 
+/// DeviceAccess ACCESS_SCOPE_* bits
+const ACCESS_SCOPE_RAW: u8 = 1 << 0; // Raw device readings
+const ACCESS_SCOPE_AGGREGATED: u8 = 1 << 1; // Aggregates computed in Cartesi
+const ACCESS_SCOPE_METADATA: u8 = 1 << 2; // Device metadata only
+const ACCESS_SCOPE_ALL: u8 = ACCESS_SCOPE_RAW | ACCESS_SCOPE_AGGREGATED | ACCESS_SCOPE_METADATA;
+/// Scopes covered by the owner-wide `marketplace_access` flag, which predates scopes:
+/// the Cartesi results this pipeline records and device metadata, but not raw readings
+const MARKETPLACE_ACCESS_SCOPES: u8 = ACCESS_SCOPE_AGGREGATED | ACCESS_SCOPE_METADATA;
+
 #[public]
 impl IoTDataPipeline {
     /// Initialize the contract with basic marketplace configuration
@@ -106,13 +133,13 @@ impl IoTDataPipeline {
         }
 
         // Generate device_id_hash from payload 
-        let device_id_hash: B256 = keccak(&payload).into();
+        let device_id_hash: B256 = keccak(&payload);
         
-        // Verify device is registered
+        // Verify device is registered and in service (suspended/decommissioned devices are rejected)
         let registry_addr = self.device_registry_address.get();
         let is_registered = self._verify_device_registration(registry_addr, device_id_hash)?;
         if !is_registered {
-            return Err(b"Device not registered".to_vec());
+            return Err(b"Device not registered or not active".to_vec());
         }
 
         // Get device owner
@@ -122,7 +149,7 @@ impl IoTDataPipeline {
         }
 
         // Create data submission record
-        let data_hash: B256 = keccak(&[&device_id_hash.0[..], &payload].concat()).into();
+        let data_hash: B256 = keccak([&device_id_hash.0[..], &payload].concat());
         let timestamp = self.vm().block_timestamp();
         
         let mut submission = self.data_submissions.setter(data_hash);
@@ -141,7 +168,7 @@ impl IoTDataPipeline {
         let new_total = self.total_submissions.get() + U256::from(1);
         self.total_submissions.set(new_total);
 
-        log(self.vm(), DataSubmitted {
+        self.emit(DataSubmitted {
             data_hash,
             device_id_hash,
             device_owner,
@@ -179,14 +206,41 @@ impl IoTDataPipeline {
         let marketplace_permission = self.marketplace_access.getter(owner).getter(consumer).get();
         
         if !marketplace_permission {
-            // Fall back to DeviceRegistry permissions
-            let registry_addr = self.device_registry_address.get();
-            return self._check_registry_access(registry_addr, owner, consumer);
+            // Fall back to DeviceAccess owner-wide permissions
+            let calldata = IDeviceAccess::has_accessCall { owner, consumer }.abi_encode();
+            return self._check_device_access(&calldata);
         }
         
         Ok(true)
     }
 
+    /// Check if consumer holds every bit of `scope` (ACCESS_SCOPE_* bitmask) on owner's data
+    pub fn has_marketplace_access_scope(&mut self, owner: Address, consumer: Address, scope: u8) -> Result<bool, Vec<u8>> {
+        Self::check_scope(scope)?;
+
+        // Contract-level marketplace permission covers MARKETPLACE_ACCESS_SCOPES only
+        if scope & !MARKETPLACE_ACCESS_SCOPES == 0 && self.marketplace_access.getter(owner).getter(consumer).get() {
+            return Ok(true);
+        }
+
+        // Fall back to DeviceAccess scoped permissions
+        let calldata = IDeviceAccess::has_access_scopeCall { owner, consumer, scope }.abi_encode();
+        self._check_device_access(&calldata)
+    }
+
+    /// Check if consumer may read the data behind a submission for every bit of `scope`
+    /// DeviceAccess's device-level check (device or owner-wide grants) is authoritative
+    pub fn has_data_access(&mut self, data_hash: B256, consumer: Address, scope: u8) -> Result<bool, Vec<u8>> {
+        Self::check_scope(scope)?;
+        let device_id_hash = self.data_submissions.getter(data_hash).device_id_hash.get();
+        if device_id_hash.is_zero() {
+            return Ok(false);
+        }
+
+        let calldata = IDeviceAccess::has_device_access_scopeCall { device_id_hash, consumer, scope }.abi_encode();
+        self._check_device_access(&calldata)
+    }
+
     // ========== Query Functions ==========
 
     /// Get total number of data submissions
@@ -202,7 +256,7 @@ impl IoTDataPipeline {
     /// Get data hashes submitted by an owner
     pub fn get_owner_data_hashes(&self, owner: Address) -> Result<Vec<B256>, Vec<u8>> {
         let hashes = self.owner_data_hashes.getter(owner);
-        let mut result = Vec::with_capacity(hashes.len() as usize);
+        let mut result = Vec::with_capacity(hashes.len());
         for i in 0..hashes.len() {
             if let Some(hash) = hashes.get(i) {
                 result.push(hash);
@@ -243,24 +297,36 @@ impl IoTDataPipeline {
         Ok(())
     }
 
+    /// Get the DeviceAccess contract consulted for data access
+    pub fn device_access(&self) -> Result<Address, Vec<u8>> {
+        Ok(self.device_access_address.get())
+    }
+
+    /// Update DeviceAccess address
+    pub fn set_device_access(&mut self, new_address: Address) -> Result<(), Vec<u8>> {
+        self.only_admin()?;
+        self.device_access_address.set(new_address);
+        Ok(())
+    }
+
     /// Update base submission fee
     pub fn set_base_fee(&mut self, new_fee: U256) -> Result<(), Vec<u8>> {
         self.only_admin()?;
         self.base_submission_fee.set(new_fee);
         
-        log(self.vm(), MarketplaceConfigUpdated {
+        self.emit(MarketplaceConfigUpdated {
             base_fee: new_fee,
         });
         
         Ok(())
     }
 
-    /// Set paused state
+    // Set paused state
     // pub fn set_paused(&mut self, paused: bool) -> Result<(), Vec<u8>> {  // REMOVED: Anti-decentralization
     //     self.only_admin()?;
     //     // self.is_paused.set(paused); // REMOVED: Anti-decentralization pattern
     //     
-    //     log(self.vm(), MarketplaceConfigUpdated {
+    //     self.emit(MarketplaceConfigUpdated {
     //         base_fee: self.base_submission_fee.get(),
     //         // is_paused: paused, // REMOVED: Anti-decentralization pattern
     //     });
@@ -276,6 +342,20 @@ impl IoTDataPipeline {
 
 // Private helper functions
 impl IoTDataPipeline {
+    /// Emit an event; unlike `stylus_sdk::prelude::log` this cannot panic, which keeps
+    /// panic formatting out of the contract
+    fn emit<T: SolEvent>(&self, event: T) {
+        let mut topics = [alloy_sol_types::abi::token::WordToken::default(); 4];
+        let _ = event.encode_topics_raw(&mut topics);
+        let count = <T::TopicList as alloy_sol_types::TopicList>::COUNT;
+        let mut data = Vec::with_capacity(32 * count);
+        for topic in &topics[..count] {
+            data.extend_from_slice(topic.as_slice());
+        }
+        event.encode_data_to(&mut data);
+        self.vm().emit_log(&data, count);
+    }
+
     /// Ensure only admin can call
     fn only_admin(&self) -> Result<(), Vec<u8>> {
         if self.vm().msg_sender() != self.admin.get() {
@@ -284,6 +364,14 @@ impl IoTDataPipeline {
         Ok(())
     }
 
+    /// Ensure `scope` is a non-empty combination of ACCESS_SCOPE_* bits
+    fn check_scope(scope: u8) -> Result<(), Vec<u8>> {
+        if scope == 0 || scope & !ACCESS_SCOPE_ALL != 0 {
+            return Err(b"Invalid access scope".to_vec());
+        }
+        Ok(())
+    }
+
     /// Verify device registration via static call to DeviceRegistry
     fn _verify_device_registration(&mut self, registry_addr: Address, device_id_hash: B256) -> Result<bool, Vec<u8>> {
         let calldata = IDeviceRegistry::is_device_registeredCall { device_id_hash }.abi_encode();
@@ -306,10 +394,10 @@ impl IoTDataPipeline {
         Ok(owner)
     }
 
-    /// Check registry-level access permissions
-    fn _check_registry_access(&mut self, registry_addr: Address, owner: Address, consumer: Address) -> Result<bool, Vec<u8>> {
-        let calldata = IDeviceRegistry::has_accessCall { owner, consumer }.abi_encode();
-        let response = self.vm().static_call(&self, registry_addr, &calldata)?;
+    /// Ask DeviceAccess an access question (`calldata` encodes one of its bool views)
+    fn _check_device_access(&mut self, calldata: &[u8]) -> Result<bool, Vec<u8>> {
+        let access_addr = self.device_access_address.get();
+        let response = self.vm().static_call(&self, access_addr, calldata)?;
         
         let (has_access,) = <(bool,)>::abi_decode(&response, true)
             .map_err(|_| b"Failed to decode access response".to_vec())?;
@@ -321,16 +409,23 @@ impl IoTDataPipeline {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use stylus_sdk::{test_helpers, alloy_primitives::address};
+    use stylus_sdk::{alloy_primitives::address, testing::*};
+
+    /// Run `f` with `sender` as msg.sender
+    fn with_sender<R>(vm: &TestVM, sender: Address, f: impl FnOnce() -> R) -> R {
+        vm.set_sender(sender);
+        f()
+    }
 
     #[test]
     fn test_basic_initialization() {
-        let mut contract = IoTDataPipeline::new();
+        let vm = TestVM::default();
+        let mut contract = IoTDataPipeline::from(&vm);
         let admin_address = address!("0000000000000000000000000000000000000001");
         let rollup_address = address!("0000000000000000000000000000000000000002");
         let registry_address = address!("0000000000000000000000000000000000000003");
 
-        test_helpers::with_sender(admin_address, || {
+        with_sender(&vm, admin_address, || {
             let result = contract.initialize(rollup_address, registry_address, U256::from(100));
             assert!(result.is_ok(), "Initialization should succeed");
             assert_eq!(contract.owner().unwrap(), admin_address);
@@ -340,11 +435,12 @@ mod tests {
 
     #[test]
     fn test_marketplace_access_control() {
-        let mut contract = IoTDataPipeline::new();
+        let vm = TestVM::default();
+        let mut contract = IoTDataPipeline::from(&vm);
         let owner_address = address!("0000000000000000000000000000000000000001");
         let consumer_address = address!("0000000000000000000000000000000000000002");
 
-        test_helpers::with_sender(owner_address, || {
+        with_sender(&vm, owner_address, || {
             // Grant access
             let result = contract.grant_marketplace_access(consumer_address);
             assert!(result.is_ok(), "Access grant should succeed");
@@ -362,4 +458,111 @@ mod tests {
             assert!(!has_access, "Consumer should not have access");
         });
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_submission_requires_active_device() {
+        let vm = TestVM::default();
+        let mut contract = IoTDataPipeline::from(&vm);
+        let admin_address = address!("0000000000000000000000000000000000000001");
+        let rollup_address = address!("0000000000000000000000000000000000000002");
+        let registry_address = address!("0000000000000000000000000000000000000003");
+        let owner_address = address!("0000000000000000000000000000000000000004");
+        let payload = b"did:lcore:pipeline-device".to_vec();
+        let device_id_hash = keccak(&payload);
+
+        with_sender(&vm, admin_address, || {
+            let _ = contract.initialize(rollup_address, registry_address, U256::ZERO);
+        });
+
+        let registered_call = IDeviceRegistry::is_device_registeredCall { device_id_hash }.abi_encode();
+        let owner_call = IDeviceRegistry::get_device_ownerCall { device_id_hash }.abi_encode();
+        vm.mock_static_call(registry_address, owner_call, Ok(owner_address.abi_encode()));
+
+        with_sender(&vm, rollup_address, || {
+            // The registry reports suspended and decommissioned devices as not registered
+            vm.mock_static_call(registry_address, registered_call.clone(), Ok(false.abi_encode()));
+            assert!(contract.submit_cartesi_result(payload.clone()).is_err());
+            assert_eq!(contract.total_submissions().unwrap(), U256::ZERO);
+
+            vm.mock_static_call(registry_address, registered_call, Ok(true.abi_encode()));
+            assert!(contract.submit_cartesi_result(payload.clone()).is_ok());
+            assert_eq!(contract.total_submissions().unwrap(), U256::from(1));
+        });
+    }
+
+    #[test]
+    fn test_data_access_follows_device_access() {
+        let vm = TestVM::default();
+        let mut contract = IoTDataPipeline::from(&vm);
+        let owner_address = address!("0000000000000000000000000000000000000001");
+        let rollup_address = address!("0000000000000000000000000000000000000002");
+        let registry_address = address!("0000000000000000000000000000000000000003");
+        let consumer_address = address!("0000000000000000000000000000000000000004");
+        let access_address = address!("0000000000000000000000000000000000000005");
+
+        let payload = b"did:lcore:sensor".to_vec();
+        let device_id_hash = keccak(&payload);
+        let data_hash = keccak([&device_id_hash.0[..], &payload].concat());
+        vm.mock_static_call(
+            registry_address,
+            IDeviceRegistry::is_device_registeredCall { device_id_hash }.abi_encode(),
+            Ok(true.abi_encode()),
+        );
+        vm.mock_static_call(
+            registry_address,
+            IDeviceRegistry::get_device_ownerCall { device_id_hash }.abi_encode(),
+            Ok(owner_address.abi_encode()),
+        );
+        let device_access = IDeviceAccess::has_device_access_scopeCall {
+            device_id_hash,
+            consumer: consumer_address,
+            scope: ACCESS_SCOPE_RAW,
+        }
+        .abi_encode();
+        let owner_access = IDeviceAccess::has_accessCall { owner: owner_address, consumer: consumer_address }.abi_encode();
+        let owner_raw_access = IDeviceAccess::has_access_scopeCall {
+            owner: owner_address,
+            consumer: consumer_address,
+            scope: ACCESS_SCOPE_RAW,
+        }
+        .abi_encode();
+
+        with_sender(&vm, rollup_address, || {
+            assert!(contract.initialize(rollup_address, registry_address, U256::ZERO).is_ok());
+            assert!(contract.set_device_access(access_address).is_ok());
+            assert_eq!(contract.device_access().unwrap(), access_address);
+            assert!(contract.submit_cartesi_result(payload.clone()).is_ok());
+        });
+
+        with_sender(&vm, owner_address, || {
+            assert!(contract.set_device_access(Address::ZERO).is_err());
+
+            // Owner-wide questions fall back to DeviceAccess
+            vm.mock_static_call(access_address, owner_access.clone(), Ok(true.abi_encode()));
+            assert!(contract.has_marketplace_access(owner_address, consumer_address).unwrap());
+
+            // The owner-wide pipeline flag does not override the device-level answer
+            assert!(contract.grant_marketplace_access(consumer_address).is_ok());
+            vm.mock_static_call(access_address, device_access.clone(), Ok(false.abi_encode()));
+            assert!(!contract.has_data_access(data_hash, consumer_address, ACCESS_SCOPE_RAW).unwrap());
+
+            vm.mock_static_call(access_address, device_access.clone(), Ok(true.abi_encode()));
+            assert!(contract.has_data_access(data_hash, consumer_address, ACCESS_SCOPE_RAW).unwrap());
+
+            // The pipeline flag covers aggregates and metadata but not raw readings
+            let scopes = ACCESS_SCOPE_AGGREGATED | ACCESS_SCOPE_METADATA;
+            assert!(contract.has_marketplace_access_scope(owner_address, consumer_address, scopes).unwrap());
+            vm.mock_static_call(access_address, owner_raw_access.clone(), Ok(false.abi_encode()));
+            assert!(!contract.has_marketplace_access_scope(owner_address, consumer_address, ACCESS_SCOPE_RAW).unwrap());
+            vm.mock_static_call(access_address, owner_raw_access.clone(), Ok(true.abi_encode()));
+            assert!(contract.has_marketplace_access_scope(owner_address, consumer_address, ACCESS_SCOPE_RAW).unwrap());
+
+            // Zero and unknown scope bits are rejected
+            assert!(contract.has_data_access(data_hash, consumer_address, 0).is_err());
+            assert!(contract.has_marketplace_access_scope(owner_address, consumer_address, 0x10).is_err());
+
+            // Unknown submissions grant nothing
+            assert!(!contract.has_data_access(B256::ZERO, consumer_address, ACCESS_SCOPE_RAW).unwrap());
+        });
+    }
+}
@@ -1,4 +1,4 @@
-#![cfg_attr(not(feature = "export-abi"), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
 
 #[cfg(feature = "export-abi")]
 fn main() {
@@ -6,5 +6,6 @@ fn main() {
     // The actual logic lives in src/lib.rs.
 }
 
-#[cfg(not(feature = "export-abi"))]
-fn main() {} 
\ No newline at end of file
+#[cfg(not(any(test, feature = "export-abi")))]
+#[no_mangle]
+pub extern "C" fn main() {} 
\ No newline at end of file
@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: UNLICENSED
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+// Keep `core::fmt` panic formatting out of the deployed code
+#[cfg(all(target_arch = "wasm32", not(any(test, feature = "export-abi"))))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+use alloc::vec::Vec;
+use p256::ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey};
+use p256::EncodedPoint;
+use stylus_sdk::prelude::*;
+
+// Pure-Rust P-256 verifier for chains without the RIP-7212 precompile. It has the
+// precompile's interface, so callers can fall back to it with the same calldata
+sol_storage! {
+    #[entrypoint]
+    pub struct P256Verifier {}
+}
+
+/// RIP-7212 input: hash || r || s || x || y
+const INPUT_LENGTH: usize = 160;
+
+#[public]
+impl P256Verifier {
+    /// Verify a P-256 signature; returns uint256(1) if it is valid and empty output otherwise
+    #[fallback]
+    pub fn fallback(&mut self, input: &[u8]) -> stylus_sdk::ArbResult {
+        if !Self::verify(input) {
+            return Ok(Vec::new());
+        }
+        let mut valid = vec![0u8; 32];
+        valid[31] = 1;
+        Ok(valid)
+    }
+}
+
+// Private helper functions
+impl P256Verifier {
+    /// Verify RIP-7212 input: a prehashed message, the (r, s) signature and the affine public key
+    fn verify(input: &[u8]) -> bool {
+        if input.len() != INPUT_LENGTH {
+            return false;
+        }
+        let (hash, rest) = input.split_at(32);
+        let (signature, point) = rest.split_at(64);
+        let Ok(signature) = Signature::from_slice(signature) else {
+            return false;
+        };
+        let point = EncodedPoint::from_affine_coordinates(point[..32].into(), point[32..].into(), false);
+        let Ok(key) = VerifyingKey::from_encoded_point(&point) else {
+            return false;
+        };
+        key.verify_prehash(hash, &signature).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::{alloy_primitives::hex, testing::*};
+
+    // NIST P-256 / SHA-256 deterministic ECDSA vectors (RFC 6979, appendix A.2.5)
+    const PUBLIC_KEY: [u8; 64] = hex!(
+        "60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6"
+        "7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299"
+    );
+    // SHA-256("sample")
+    const SAMPLE_HASH: [u8; 32] = hex!("af2bdbe1aa9b6ec1e2ade1d694f41fc71a831d0268e9891562113d8a62add1bf");
+    const SAMPLE_SIGNATURE: [u8; 64] = hex!(
+        "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716"
+        "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8"
+    );
+    // SHA-256("test")
+    const TEST_HASH: [u8; 32] = hex!("9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08");
+    const TEST_SIGNATURE: [u8; 64] = hex!(
+        "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367"
+        "019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083"
+    );
+
+    fn input(hash: &[u8], signature: &[u8], public_key: &[u8]) -> Vec<u8> {
+        [hash, signature, public_key].concat()
+    }
+
+    #[test]
+    fn test_nist_vectors() {
+        assert!(P256Verifier::verify(&input(&SAMPLE_HASH, &SAMPLE_SIGNATURE, &PUBLIC_KEY)));
+        assert!(P256Verifier::verify(&input(&TEST_HASH, &TEST_SIGNATURE, &PUBLIC_KEY)));
+
+        // Signatures do not verify against the wrong message or with tampered scalars
+        assert!(!P256Verifier::verify(&input(&TEST_HASH, &SAMPLE_SIGNATURE, &PUBLIC_KEY)));
+        let mut tampered = SAMPLE_SIGNATURE;
+        tampered[63] ^= 1;
+        assert!(!P256Verifier::verify(&input(&SAMPLE_HASH, &tampered, &PUBLIC_KEY)));
+        let zero_r = [&[0u8; 32], &SAMPLE_SIGNATURE[32..]].concat();
+        assert!(!P256Verifier::verify(&input(&SAMPLE_HASH, &zero_r, &PUBLIC_KEY)));
+
+        // Points off the curve and malformed input are rejected
+        let mut off_curve = PUBLIC_KEY;
+        off_curve[63] ^= 1;
+        assert!(!P256Verifier::verify(&input(&SAMPLE_HASH, &SAMPLE_SIGNATURE, &off_curve)));
+        assert!(!P256Verifier::verify(&input(&SAMPLE_HASH, &SAMPLE_SIGNATURE, &PUBLIC_KEY[..63])));
+        assert!(!P256Verifier::verify(&[]));
+    }
+
+    #[test]
+    fn test_precompile_interface() {
+        let vm = TestVM::default();
+        let mut contract = P256Verifier::from(&vm);
+
+        let valid = contract.fallback(&input(&SAMPLE_HASH, &SAMPLE_SIGNATURE, &PUBLIC_KEY)).unwrap();
+        let mut expected = [0u8; 32];
+        expected[31] = 1;
+        assert_eq!(valid, expected.to_vec());
+
+        let invalid = contract.fallback(&input(&TEST_HASH, &SAMPLE_SIGNATURE, &PUBLIC_KEY)).unwrap();
+        assert!(invalid.is_empty());
+    }
+}
@@ -42,12 +42,42 @@ if [ ! -f "iotdatapipeline_address.txt" ]; then
     exit 1
 fi
 
+if [ ! -f "devicekeys_address.txt" ]; then
+    echo -e "${RED}❌ DeviceKeys address not found. Run deploy.sh first.${NC}"
+    exit 1
+fi
+
+if [ ! -f "deviceaccess_address.txt" ]; then
+    echo -e "${RED}❌ DeviceAccess address not found. Run deploy.sh first.${NC}"
+    exit 1
+fi
+
 DEVICE_REGISTRY=$(cat deviceregistry_address.txt)
 IOT_PIPELINE=$(cat iotdatapipeline_address.txt)
+DEVICE_KEYS=$(cat devicekeys_address.txt)
+DEVICE_ACCESS=$(cat deviceaccess_address.txt)
 
 echo -e "${YELLOW}📋 Testing contracts:${NC}"
 echo -e "${YELLOW}DeviceRegistry: $DEVICE_REGISTRY${NC}"
 echo -e "${YELLOW}IoTDataPipeline: $IOT_PIPELINE${NC}"
+echo -e "${YELLOW}DeviceKeys: $DEVICE_KEYS${NC}"
+echo -e "${YELLOW}DeviceAccess: $DEVICE_ACCESS${NC}"
+
+# Generate a P-256 device key and sign DeviceKeys.registration_digest(device_id, owner) with it
+# Sets PUBLIC_KEY (uncompressed SEC1 hex) and PROOF (64-byte r || s as 0x-prefixed bytes)
+make_registration_proof() {
+    local device_keys="$1" device_id="$2" owner="$3" rpc_url="$4"
+    local key_file digest
+    key_file=$(mktemp)
+    openssl ecparam -name prime256v1 -genkey -noout -out "$key_file"
+    PUBLIC_KEY=$(openssl ec -in "$key_file" -pubout -conv_form uncompressed -outform DER 2>/dev/null | tail -c 65 | xxd -p -c 65)
+    digest=$(cast call "$device_keys" "registration_digest(string,address)(bytes32)" "$device_id" "$owner" --rpc-url "$rpc_url")
+    PROOF=0x$(printf '%s' "${digest#0x}" | xxd -r -p \
+        | openssl pkeyutl -sign -inkey "$key_file" \
+        | openssl asn1parse -inform DER \
+        | awk -F: '/INTEGER/ { printf "%64s", $NF }' | tr ' ' 0)
+    rm -f "$key_file"
+}
 
 # =============================================================================
 # Test 1: Device Registration
@@ -57,14 +87,16 @@ echo -e "\n${BLUE}🧪 Test 1: Device Registration${NC}"
 
 DEVICE_ID="did:lcore:test-device-001"
 DID_DOCUMENT='{"id":"did:lcore:test-device-001","type":"IoT"}'
-PUBLIC_KEY="0xabcdef1234567890"
 DEVICE_TYPE="environmental_sensor"
 METADATA='{"location":"test_lab","domain":"environmental"}'
 
 echo -e "${YELLOW}Registering device: $DEVICE_ID${NC}"
 
-if cast send $DEVICE_REGISTRY "register_device(string,string,string,string,string)" \
-    "$DEVICE_ID" "$DID_DOCUMENT" "$PUBLIC_KEY" "$DEVICE_TYPE" "$METADATA" \
+OWNER_ADDRESS=$(cast wallet address --private-key $PRIVATE_KEY)
+make_registration_proof $DEVICE_KEYS "$DEVICE_ID" $OWNER_ADDRESS $RPC_URL
+
+if cast send $DEVICE_REGISTRY "register_device(string,string,string,string,string,bytes)" \
+    "$DEVICE_ID" "$DID_DOCUMENT" "$PUBLIC_KEY" "$DEVICE_TYPE" "$METADATA" "$PROOF" \
     --value 100 --private-key $PRIVATE_KEY --rpc-url $RPC_URL > /dev/null 2>&1; then
     echo -e "${GREEN}✅ Device registration successful${NC}"
 else
@@ -103,7 +135,7 @@ CONSUMER_ADDRESS="0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
 
 echo -e "${YELLOW}Granting access to consumer: $CONSUMER_ADDRESS${NC}"
 
-if cast send $DEVICE_REGISTRY "grant_access(address,uint256)" $CONSUMER_ADDRESS 0 \
+if cast send $DEVICE_ACCESS "grant_access(address,uint256)" $CONSUMER_ADDRESS 0 \
     --private-key $PRIVATE_KEY --rpc-url $RPC_URL > /dev/null 2>&1; then
     echo -e "${GREEN}✅ Access granted successfully${NC}"
 else
@@ -113,7 +145,7 @@ fi
 
 # Verify access
 OWNER_ADDRESS=$(cast wallet address --private-key $PRIVATE_KEY)
-HAS_ACCESS=$(cast call $DEVICE_REGISTRY "has_access(address,address)" $OWNER_ADDRESS $CONSUMER_ADDRESS --rpc-url $RPC_URL)
+HAS_ACCESS=$(cast call $DEVICE_ACCESS "has_access(address,address)" $OWNER_ADDRESS $CONSUMER_ADDRESS --rpc-url $RPC_URL)
 
 if [ "$HAS_ACCESS" = "true" ]; then
     echo -e "${GREEN}✅ Access control verification successful${NC}"
@@ -210,6 +242,8 @@ echo -e "${GREEN}✅ Cross-Contract Integration: PASSED${NC}"
 echo -e "\n${BLUE}📋 Contract Addresses:${NC}"
 echo -e "${YELLOW}DeviceRegistry: $DEVICE_REGISTRY${NC}"
 echo -e "${YELLOW}IoTDataPipeline: $IOT_PIPELINE${NC}"
+echo -e "${YELLOW}DeviceKeys: $DEVICE_KEYS${NC}"
+echo -e "${YELLOW}DeviceAccess: $DEVICE_ACCESS${NC}"
 
 echo -e "\n${BLUE}🚀 Ready for lcore-node integration!${NC}"
 echo -e "\n${YELLOW}Next steps:${NC}"
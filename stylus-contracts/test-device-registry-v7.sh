@@ -15,6 +15,8 @@ NC='\033[0m' # No Color
 # Contract addresses
 DEVICE_REGISTRY_V8="0xc3cf289e7d0167a857c28662e673ca7a06d3a461"
 IOT_DATA_PIPELINE_V5="0xc58451db383aaadac88895bf20d7e08db2c92b41"
+# DeviceKeys deployment that checks registration proofs for DEVICE_REGISTRY_V8
+DEVICE_KEYS="${DEVICE_KEYS:?Set DEVICE_KEYS to the DeviceKeys address}"
 
 # Network configuration
 KC_CHAIN_RPC_URL="https://rpc.devnet.alchemy.com/7eade438-d743-4dc5-ac64-3480de391200"
@@ -28,6 +30,22 @@ echo -e "IoTDataPipeline v5: ${GREEN}$IOT_DATA_PIPELINE_V5${NC}"
 echo -e "RPC URL: ${GREEN}$KC_CHAIN_RPC_URL${NC}"
 echo ""
 
+# Generate a P-256 device key and sign DeviceKeys.registration_digest(device_id, owner) with it
+# Sets PUBLIC_KEY (uncompressed SEC1 hex) and PROOF (64-byte r || s as 0x-prefixed bytes)
+make_registration_proof() {
+    local device_keys="$1" device_id="$2" owner="$3" rpc_url="$4"
+    local key_file digest
+    key_file=$(mktemp)
+    openssl ecparam -name prime256v1 -genkey -noout -out "$key_file"
+    PUBLIC_KEY=$(openssl ec -in "$key_file" -pubout -conv_form uncompressed -outform DER 2>/dev/null | tail -c 65 | xxd -p -c 65)
+    digest=$(cast call "$device_keys" "registration_digest(string,address)(bytes32)" "$device_id" "$owner" --rpc-url "$rpc_url")
+    PROOF=0x$(printf '%s' "${digest#0x}" | xxd -r -p \
+        | openssl pkeyutl -sign -inkey "$key_file" \
+        | openssl asn1parse -inform DER \
+        | awk -F: '/INTEGER/ { printf "%64s", $NF }' | tr ' ' 0)
+    rm -f "$key_file"
+}
+
 # Function to run a test and report results
 run_test() {
     local test_name="$1"
@@ -128,15 +146,17 @@ echo -e "${YELLOW}Testing free device registration...${NC}"
 
 test_device_id="test_device_$(date +%s)"
 test_did="{\"id\":\"did:example:$test_device_id\"}"
-test_pubkey="0x1234567890123456789012345678901234567890123456789012345678901234"
+test_owner=$(cast wallet address --mnemonic "$MNEMONIC")
+make_registration_proof $DEVICE_KEYS "$test_device_id" $test_owner $KC_CHAIN_RPC_URL
 
 registration_result=$(cast send $DEVICE_REGISTRY_V8 \
-    "register_device(string,string,string,string,string)" \
+    "register_device(string,string,string,string,string,bytes)" \
     "$test_device_id" \
     "$test_did" \
-    "$test_pubkey" \
+    "$PUBLIC_KEY" \
     "sensor" \
     "{\"type\":\"test\"}" \
+    "$PROOF" \
     --mnemonic "$MNEMONIC" \
     --rpc-url $KC_CHAIN_RPC_URL \
     --gas-limit 500000 \